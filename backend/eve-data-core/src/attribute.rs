@@ -21,6 +21,81 @@ pub enum Attribute {
     SenarySkill,
     SenarySkillLevel,
 
+    HiSlots,
+    MedSlots,
+    LowSlots,
+    RigSlots,
+    TurretSlots,
+    LauncherSlots,
+    CpuOutput,
+    PowerOutput,
+    CalibrationOutput,
+
+    Cpu,
+    Power,
+    Calibration,
+    CpuMultiplier,
+    PowerOutputMultiplier,
+    PowerIncrease,
+    CpuOutputBonus,
+    PowerOutputBonus,
+
+    HiSlotModifier,
+    MedSlotModifier,
+    LowSlotModifier,
+    TurretSlotModifier,
+    LauncherSlotModifier,
+
+    StructureHp,
+    ArmorHp,
+    ShieldHp,
+    ShieldEmResonance,
+    ShieldExplosiveResonance,
+    ShieldKineticResonance,
+    ShieldThermalResonance,
+    ArmorEmResonance,
+    ArmorExplosiveResonance,
+    ArmorKineticResonance,
+    ArmorThermalResonance,
+    HullEmResonance,
+    HullExplosiveResonance,
+    HullKineticResonance,
+    HullThermalResonance,
+    ArmorHpBonusAdd,
+    ShieldCapacityBonus,
+    ArmorHpMultiplier,
+    ShieldCapacityMultiplier,
+    StructureHpMultiplier,
+
+    DamageMultiplier,
+    MissileDamageMultiplier,
+    RateOfFire,
+    RateOfFireMultiplier,
+    EmDamage,
+    ExplosiveDamage,
+    KineticDamage,
+    ThermalDamage,
+    ChargeGroup1,
+    ChargeGroup2,
+    ChargeGroup3,
+    ChargeGroup4,
+    ChargeGroup5,
+    ChargeSize,
+
+    CapacitorCapacity,
+    CapacitorRechargeRate,
+    CapacitorNeed,
+    Duration,
+    CapacitorBonus,
+    CapacitorCapacityMultiplier,
+    CapacitorRechargeRateMultiplier,
+
+    MaxVelocity,
+    Mass,
+    SpeedFactor,
+    SpeedBoostFactor,
+    MassAddition,
+
     Other(i32),
 }
 
@@ -48,6 +123,81 @@ impl Attribute {
             1290 => Self::SenarySkill,
             1288 => Self::SenarySkillLevel,
 
+            14 => Self::HiSlots,
+            13 => Self::MedSlots,
+            12 => Self::LowSlots,
+            1137 => Self::RigSlots,
+            102 => Self::TurretSlots,
+            101 => Self::LauncherSlots,
+            48 => Self::CpuOutput,
+            11 => Self::PowerOutput,
+            1132 => Self::CalibrationOutput,
+
+            50 => Self::Cpu,
+            30 => Self::Power,
+            1153 => Self::Calibration,
+            202 => Self::CpuMultiplier,
+            145 => Self::PowerOutputMultiplier,
+            549 => Self::PowerIncrease,
+            424 => Self::CpuOutputBonus,
+            313 => Self::PowerOutputBonus,
+
+            1374 => Self::HiSlotModifier,
+            1375 => Self::MedSlotModifier,
+            1376 => Self::LowSlotModifier,
+            1368 => Self::TurretSlotModifier,
+            1369 => Self::LauncherSlotModifier,
+
+            9 => Self::StructureHp,
+            265 => Self::ArmorHp,
+            263 => Self::ShieldHp,
+            271 => Self::ShieldEmResonance,
+            272 => Self::ShieldExplosiveResonance,
+            273 => Self::ShieldKineticResonance,
+            274 => Self::ShieldThermalResonance,
+            267 => Self::ArmorEmResonance,
+            268 => Self::ArmorExplosiveResonance,
+            269 => Self::ArmorKineticResonance,
+            270 => Self::ArmorThermalResonance,
+            113 => Self::HullEmResonance,
+            111 => Self::HullExplosiveResonance,
+            109 => Self::HullKineticResonance,
+            110 => Self::HullThermalResonance,
+            1159 => Self::ArmorHpBonusAdd,
+            72 => Self::ShieldCapacityBonus,
+            148 => Self::ArmorHpMultiplier,
+            146 => Self::ShieldCapacityMultiplier,
+            150 => Self::StructureHpMultiplier,
+
+            64 => Self::DamageMultiplier,
+            213 => Self::MissileDamageMultiplier,
+            51 => Self::RateOfFire,
+            204 => Self::RateOfFireMultiplier,
+            114 => Self::EmDamage,
+            116 => Self::ExplosiveDamage,
+            117 => Self::KineticDamage,
+            118 => Self::ThermalDamage,
+            604 => Self::ChargeGroup1,
+            605 => Self::ChargeGroup2,
+            606 => Self::ChargeGroup3,
+            609 => Self::ChargeGroup4,
+            610 => Self::ChargeGroup5,
+            128 => Self::ChargeSize,
+
+            482 => Self::CapacitorCapacity,
+            55 => Self::CapacitorRechargeRate,
+            6 => Self::CapacitorNeed,
+            73 => Self::Duration,
+            67 => Self::CapacitorBonus,
+            147 => Self::CapacitorCapacityMultiplier,
+            144 => Self::CapacitorRechargeRateMultiplier,
+
+            37 => Self::MaxVelocity,
+            4 => Self::Mass,
+            20 => Self::SpeedFactor,
+            567 => Self::SpeedBoostFactor,
+            796 => Self::MassAddition,
+
             i => Self::Other(i),
         }
     }
@@ -0,0 +1,339 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use super::{Attribute, Effect, FitError, Fitting, Modifier, SkillLevel, Type, TypeDB, TypeID};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Usage {
+    pub used: f64,
+    pub available: f64,
+}
+
+impl Usage {
+    fn exceeded(&self) -> bool {
+        // Leave a little room for floating point noise
+        self.used > self.available + 0.01
+    }
+}
+
+/// Fitting resources used by a fit compared to what the hull provides.
+///
+/// This is a small subset of dogma: the modifiers of the hull's effects (hull and role bonuses)
+/// and of the pilot's skills are applied to the hull and its modules, along with fitting modules,
+/// subsystems and implants. Skill bonuses scale with the trained level, hull bonuses are applied
+/// as the hull's attributes hold them.
+#[derive(Debug, Default)]
+pub struct FittingStats {
+    pub hi_slots: Usage,
+    pub med_slots: Usage,
+    pub low_slots: Usage,
+    pub rig_slots: Usage,
+    pub turrets: Usage,
+    pub launchers: Usage,
+    pub cpu: Usage,
+    pub powergrid: Usage,
+    pub calibration: Usage,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FitProblem {
+    pub resource: &'static str,
+    pub used: f64,
+    pub available: f64,
+}
+
+impl fmt::Display for FitProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Fit needs {} {} but only {} is available",
+            round(self.used),
+            self.resource,
+            round(self.available)
+        )
+    }
+}
+
+fn round(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+pub(crate) fn attribute(the_type: &Type, attribute: Attribute) -> f64 {
+    the_type.attributes.get(&attribute).copied().unwrap_or(0.0) as f64
+}
+
+pub(crate) fn skill_bonus(
+    skills: &HashMap<TypeID, SkillLevel>,
+    (skill_id, per_level): (TypeID, f64),
+) -> f64 {
+    let level = skills.get(&skill_id).copied().unwrap_or(0);
+    1.0 + per_level * level as f64 / 100.0
+}
+
+// Dogma operations, in the order they are applied
+const PRE_ASSIGN: i32 = -1;
+const PRE_MUL: i32 = 0;
+const PRE_DIV: i32 = 1;
+const MOD_ADD: i32 = 2;
+const MOD_SUB: i32 = 3;
+const POST_MUL: i32 = 4;
+const POST_DIV: i32 = 5;
+const POST_PERCENT: i32 = 6;
+const POST_ASSIGN: i32 = 7;
+
+type Modifications = HashMap<Attribute, Vec<(i32, f64)>>;
+
+fn apply(base: f64, modifications: Option<&Vec<(i32, f64)>>) -> f64 {
+    let mut modifications = modifications.cloned().unwrap_or_default();
+    modifications.sort_by_key(|&(operation, _)| operation);
+    modifications
+        .into_iter()
+        .fold(base, |value, (operation, amount)| match operation {
+            PRE_ASSIGN | POST_ASSIGN => amount,
+            PRE_MUL | POST_MUL => value * amount,
+            PRE_DIV | POST_DIV => value / amount,
+            MOD_ADD => value + amount,
+            MOD_SUB => value - amount,
+            POST_PERCENT => value * (1.0 + amount / 100.0),
+            _ => value,
+        })
+}
+
+/// Where the modifiers of the hull and the pilot's skills end up
+#[derive(Default)]
+struct Modified {
+    ship: Modifications,
+    modules: HashMap<TypeID, Modifications>,
+}
+
+impl Modified {
+    fn ship_attribute(&self, hull: &Type, which: Attribute) -> f64 {
+        apply(attribute(hull, which), self.ship.get(&which))
+    }
+
+    fn module_attribute(&self, module: &Type, which: Attribute) -> f64 {
+        apply(
+            attribute(module, which),
+            self.modules.get(&module.id).and_then(|m| m.get(&which)),
+        )
+    }
+
+    fn add_ship(&mut self, which: Attribute, operation: i32, amount: f64) {
+        self.ship
+            .entry(which)
+            .or_default()
+            .push((operation, amount));
+    }
+
+    fn add(&mut self, modifier: &Modifier, amount: f64, from_ship: bool, modules: &[&Arc<Type>]) {
+        // Only the ship and what is fitted to it matter here
+        let on_ship = match modifier.domain.as_deref() {
+            Some("shipID") => true,
+            Some("itemID") => from_ship,
+            _ => false,
+        };
+        if !on_ship {
+            return;
+        }
+
+        if modifier.func == "ItemModifier" {
+            self.add_ship(modifier.modified, modifier.operation, amount);
+            return;
+        }
+
+        for module in modules {
+            let applies = match modifier.func.as_str() {
+                "LocationModifier" => true,
+                "LocationGroupModifier" => modifier.group == Some(module.group_id),
+                "LocationRequiredSkillModifier" => modifier
+                    .skill
+                    .is_some_and(|skill| module.skill_requirements.contains_key(&skill)),
+                _ => false,
+            };
+            if applies {
+                self.modules
+                    .entry(module.id)
+                    .or_default()
+                    .entry(modifier.modified)
+                    .or_default()
+                    .push((modifier.operation, amount));
+            }
+        }
+    }
+}
+
+impl FittingStats {
+    pub fn calculate(
+        fit: &Fitting,
+        skills: &HashMap<TypeID, SkillLevel>,
+        implants: &[TypeID],
+    ) -> Result<FittingStats, FitError> {
+        let hull = TypeDB::load_type(fit.hull)?;
+        let mut stats = FittingStats::default();
+
+        let module_ids: Vec<TypeID> = fit.modules.keys().copied().collect();
+        let modules = TypeDB::load_types(&module_ids)?;
+        let mut fitted = Vec::new();
+        for id in &module_ids {
+            fitted.push(modules[id].as_ref().ok_or(FitError::InvalidModule)?);
+        }
+
+        // The hull applies its bonuses as they are, skill bonuses are per level
+        let skill_ids: Vec<TypeID> = skills
+            .iter()
+            .filter(|(_, &level)| level > 0)
+            .map(|(&id, _)| id)
+            .collect();
+        let mut sources = vec![(hull.clone(), 1.0, true)];
+        for skill in TypeDB::load_types(&skill_ids)?.into_values().flatten() {
+            let level = skills[&skill.id] as f64;
+            sources.push((skill, level, false));
+        }
+
+        let effects: Vec<Effect> = sources
+            .iter()
+            .flat_map(|(source, _, _)| source.effects.iter().copied())
+            .collect();
+        let modifiers = TypeDB::effect_modifiers(&effects)?;
+
+        let mut modified = Modified::default();
+        for (source, multiplier, from_ship) in &sources {
+            for effect in &source.effects {
+                for modifier in modifiers.get(effect).into_iter().flatten() {
+                    let amount = attribute(source, modifier.modifying) * multiplier;
+                    modified.add(modifier, amount, *from_ship, &fitted);
+                }
+            }
+        }
+
+        for module in &fitted {
+            let count = fit.modules[&module.id] as f64;
+
+            match module.slot() {
+                Some("high") => stats.hi_slots.used += count,
+                Some("med") => stats.med_slots.used += count,
+                Some("low") => stats.low_slots.used += count,
+                Some("rig") => stats.rig_slots.used += count,
+                Some(_) => continue,
+                None => (),
+            }
+
+            if module.effects.contains(&Effect::subsystem()) {
+                for (modifier, which) in [
+                    (Attribute::HiSlotModifier, Attribute::HiSlots),
+                    (Attribute::MedSlotModifier, Attribute::MedSlots),
+                    (Attribute::LowSlotModifier, Attribute::LowSlots),
+                    (Attribute::TurretSlotModifier, Attribute::TurretSlots),
+                    (Attribute::LauncherSlotModifier, Attribute::LauncherSlots),
+                    (Attribute::CpuOutput, Attribute::CpuOutput),
+                    (Attribute::PowerOutput, Attribute::PowerOutput),
+                ] {
+                    modified.add_ship(which, MOD_ADD, attribute(module, modifier));
+                }
+            }
+
+            if module.effects.contains(&Effect::turret_fitted()) {
+                stats.turrets.used += count;
+            } else if module.effects.contains(&Effect::launcher_fitted()) {
+                stats.launchers.used += count;
+            }
+
+            stats.cpu.used += modified.module_attribute(module, Attribute::Cpu) * count;
+            stats.powergrid.used += modified.module_attribute(module, Attribute::Power) * count;
+            stats.calibration.used +=
+                modified.module_attribute(module, Attribute::Calibration) * count;
+
+            modified.add_ship(
+                Attribute::PowerOutput,
+                MOD_ADD,
+                attribute(module, Attribute::PowerIncrease) * count,
+            );
+            for (multiplier, which) in [
+                (Attribute::CpuMultiplier, Attribute::CpuOutput),
+                (Attribute::PowerOutputMultiplier, Attribute::PowerOutput),
+            ] {
+                if let Some(&multiplier) = module.attributes.get(&multiplier) {
+                    modified.add_ship(which, POST_MUL, (multiplier as f64).powf(count));
+                }
+            }
+        }
+
+        for implant in TypeDB::load_types(implants)?.values().flatten() {
+            for (bonus, which) in [
+                (Attribute::CpuOutputBonus, Attribute::CpuOutput),
+                (Attribute::PowerOutputBonus, Attribute::PowerOutput),
+            ] {
+                modified.add_ship(which, POST_PERCENT, attribute(implant, bonus));
+            }
+        }
+
+        stats.hi_slots.available = modified.ship_attribute(&hull, Attribute::HiSlots);
+        stats.med_slots.available = modified.ship_attribute(&hull, Attribute::MedSlots);
+        stats.low_slots.available = modified.ship_attribute(&hull, Attribute::LowSlots);
+        stats.rig_slots.available = modified.ship_attribute(&hull, Attribute::RigSlots);
+        stats.turrets.available = modified.ship_attribute(&hull, Attribute::TurretSlots);
+        stats.launchers.available = modified.ship_attribute(&hull, Attribute::LauncherSlots);
+        stats.cpu.available = modified.ship_attribute(&hull, Attribute::CpuOutput);
+        stats.powergrid.available = modified.ship_attribute(&hull, Attribute::PowerOutput);
+        stats.calibration.available = modified.ship_attribute(&hull, Attribute::CalibrationOutput);
+
+        Ok(stats)
+    }
+
+    pub fn problems(&self) -> Vec<FitProblem> {
+        [
+            ("high slots", self.hi_slots),
+            ("mid slots", self.med_slots),
+            ("low slots", self.low_slots),
+            ("rig slots", self.rig_slots),
+            ("turret hardpoints", self.turrets),
+            ("launcher hardpoints", self.launchers),
+            ("CPU", self.cpu),
+            ("powergrid", self.powergrid),
+            ("calibration", self.calibration),
+        ]
+        .iter()
+        .filter(|(_, usage)| usage.exceeded())
+        .map(|&(resource, usage)| FitProblem {
+            resource,
+            used: usage.used,
+            available: usage.available,
+        })
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FittingStats, HashMap};
+    use crate::Fitting;
+
+    fn all_fives() -> HashMap<i32, i16> {
+        let mut skills = HashMap::new();
+        for &skill_id in &[3426, 3413, 3318, 11207] {
+            skills.insert(skill_id, 5);
+        }
+        skills
+    }
+
+    #[test]
+    fn test_valid_fit() {
+        let fit = Fitting::from_dna("17736:3057;4:2456;2:4383_;1:12816;2::").unwrap();
+        let stats = FittingStats::calculate(&fit, &all_fives(), &[]).unwrap();
+
+        assert_eq!(stats.turrets.used, 4.0);
+        assert!(stats.cpu.used > 0.0);
+        assert!(stats.problems().is_empty());
+    }
+
+    #[test]
+    fn test_too_many_turrets() {
+        let fit = Fitting::from_dna("17736:3057;9::").unwrap();
+        let stats = FittingStats::calculate(&fit, &all_fives(), &[]).unwrap();
+        let problems = stats.problems();
+
+        assert!(problems.iter().any(|p| p.resource == "high slots"));
+        assert!(problems.iter().any(|p| p.resource == "turret hardpoints"));
+    }
+}
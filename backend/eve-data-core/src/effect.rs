@@ -1,3 +1,5 @@
+use super::{Attribute, TypeID};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Effect(pub i32); // Trying the Effect implementation in a different way to see what works better.
 impl Effect {
@@ -13,4 +15,25 @@ impl Effect {
     pub fn rig_slot() -> Effect {
         Effect(2663)
     }
+    pub fn launcher_fitted() -> Effect {
+        Effect(40)
+    }
+    pub fn turret_fitted() -> Effect {
+        Effect(42)
+    }
+    pub fn subsystem() -> Effect {
+        Effect(3772)
+    }
+}
+
+/// A dogma modifier of an effect. Only modifiers of fitting attributes are kept by sde_import.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Modifier {
+    pub func: String,
+    pub domain: Option<String>,
+    pub modified: Attribute,
+    pub modifying: Attribute,
+    pub operation: i32,
+    pub skill: Option<TypeID>,
+    pub group: Option<i32>,
 }
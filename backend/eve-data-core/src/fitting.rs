@@ -1,5 +1,6 @@
 use std::{
-    collections::{BTreeMap, BTreeSet},
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt,
     num::ParseIntError,
 };
 
@@ -24,10 +25,37 @@ pub enum FitError {
     InvalidCount,
     #[error("only ships can fly")]
     InvalidHull,
+    #[error("{0}")]
+    EftLine(EftError),
     #[error("internal error: {0}")]
     Internal(#[source] TypeError),
 }
 
+/// A line of an EFT paste that could not be parsed
+#[derive(Debug, Clone, PartialEq)]
+pub struct EftError {
+    /// Line number in the paste, starting at 1
+    pub line: usize,
+    pub text: String,
+    pub reason: &'static str,
+    /// Type names close to `text`, when it is an unknown item
+    pub suggestions: Vec<String>,
+}
+
+impl fmt::Display for EftError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {} \"{}\"", self.line, self.reason, self.text)?;
+        if !self.suggestions.is_empty() {
+            write!(
+                f,
+                ", did you mean \"{}\"?",
+                self.suggestions.join("\" or \"")
+            )?;
+        }
+        Ok(())
+    }
+}
+
 impl From<ParseIntError> for FitError {
     fn from(_: ParseIntError) -> Self {
         FitError::ParseError
@@ -43,6 +71,62 @@ impl From<TypeError> for FitError {
     }
 }
 
+const MAX_SUGGESTIONS: usize = 5;
+
+/// Type names similar to `text`. Besides a case insensitive match, each word is in turn replaced
+/// by a wildcard and cut down to either half, so a typo in a single word still finds the item.
+fn similar_names(text: &str) -> Result<Vec<String>, FitError> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let mut patterns = vec![words.join(" ")];
+    for (i, word) in words.iter().enumerate() {
+        let half = word.chars().count() / 2;
+        let prefix: String = word.chars().take(half).collect();
+        let suffix: String = word.chars().skip(half).collect();
+        for replacement in &["%".to_string(), prefix + "%", "%".to_string() + &suffix] {
+            let mut pattern = words.clone();
+            pattern[i] = replacement;
+            patterns.push(pattern.join(" "));
+        }
+    }
+
+    let mut ids = Vec::new();
+    for pattern in patterns {
+        match TypeDB::id_of_fuzzy(&pattern) {
+            Ok(id) if !ids.contains(&id) => ids.push(id),
+            Ok(_) | Err(TypeError::NothingMatched | TypeError::MultipleMatches) => (),
+            Err(e) => return Err(e.into()),
+        }
+        if ids.len() == MAX_SUGGESTIONS {
+            break;
+        }
+    }
+
+    let names = TypeDB::names_of(&ids)?;
+    Ok(ids.iter().filter_map(|id| names.get(id).cloned()).collect())
+}
+
+fn line_error(line: usize, text: &str, reason: &'static str) -> FitError {
+    FitError::EftLine(EftError {
+        line,
+        text: text.to_string(),
+        reason,
+        suggestions: Vec::new(),
+    })
+}
+
+fn unknown_type(line: usize, text: &str) -> FitError {
+    let suggestions = match similar_names(text) {
+        Ok(suggestions) => suggestions,
+        Err(e) => return e,
+    };
+    FitError::EftLine(EftError {
+        line,
+        text: text.to_string(),
+        reason: "unknown item type",
+        suggestions,
+    })
+}
+
 impl Fitting {
     pub fn from_dna(dna: &str) -> Result<Fitting, FitError> {
         let mut pieces = dna.split(':');
@@ -117,29 +201,66 @@ impl Fitting {
         Ok(dna + ":")
     }
 
+    /// Resolves the type names of an EFT paste. Names are matched in English first, anything
+    /// left over is matched in whichever client language fits the rest of the paste best.
+    fn eft_type_ids(eft: &str) -> Result<HashMap<&str, TypeID>, FitError> {
+        let names: Vec<&str> = eft
+            .lines()
+            .map(str::trim)
+            .filter_map(|line| {
+                if line.is_empty() || line.starts_with("[Empty ") {
+                    None
+                } else if line.starts_with('[') && line.ends_with(']') && line.contains(',') {
+                    line[1..].split(',').next().map(str::trim)
+                } else {
+                    line.split(" x").next()
+                }
+            })
+            .collect();
+
+        let mut ids = TypeDB::ids_of(&names)?;
+        let unresolved: Vec<&str> = names
+            .into_iter()
+            .filter(|name| !ids.contains_key(name))
+            .collect();
+        if unresolved.is_empty() {
+            return Ok(ids);
+        }
+
+        if let Some(language) = TypeDB::detect_language(&unresolved)? {
+            ids.extend(TypeDB::ids_of_localized(&unresolved, &language)?);
+        }
+        Ok(ids)
+    }
+
     pub fn from_eft(eft: &str) -> Result<Vec<Fitting>, FitError> {
         let mut fittings = Vec::new();
         let mut section = 0;
+        let mut has_drone_bay = false;
+        let mut last_is_cargo = None;
         let section_count = eft.trim().lines().filter(|&l| l == "").count();
+        let ids = Self::eft_type_ids(eft)?;
 
-        for line in eft.lines() {
+        for (number, line) in eft.lines().enumerate() {
+            let number = number + 1;
             let line = line.trim();
 
             if line.starts_with('[') && line.ends_with(']') && line.contains(',') {
                 let line = line.strip_prefix('[').unwrap().strip_suffix(']').unwrap();
                 let mut pieces = line.splitn(2, ',');
                 let hull_name = pieces.next().unwrap().trim(); // 1st elmt
-                let ship_name = pieces.next();
-                if ship_name.is_none() {
-                    return Err(FitError::ParseError);
-                }
-                let hull = TypeDB::id_of(hull_name)?;
+                let hull = match ids.get(hull_name) {
+                    Some(&hull) => hull,
+                    None => return Err(unknown_type(number, hull_name)),
+                };
                 fittings.push(Fitting {
                     hull,
                     cargo: BTreeMap::new(),
                     modules: BTreeMap::new(),
                 });
                 section = 0;
+                has_drone_bay = false;
+                last_is_cargo = None;
             } else if let Some(fit) = fittings.last_mut() {
                 if line.starts_with("[Empty ") {
                     continue;
@@ -149,21 +270,31 @@ impl Fitting {
                 } else {
                     let mut pieces = line.split(" x");
                     let type_name = pieces.next().unwrap(); // 1st elmt
-                    let type_id = TypeDB::id_of(type_name)?;
+                    let type_id = match ids.get(type_name) {
+                        Some(&type_id) => type_id,
+                        None => return Err(unknown_type(number, type_name)),
+                    };
 
                     let (count, stacked) = match pieces.next() {
                         None => (1, false),
-                        Some(s) => (s.parse()?, true),
+                        Some(s) => match s.parse() {
+                            Ok(count) => (count, true),
+                            Err(_) => return Err(line_error(number, s, "invalid item count")),
+                        },
                     };
 
-                    let is_cargo = if section >= section_count {
-                        // Sections are high,med,low,rig,subsystem,drone, then cargo
-                        true
-                    } else {
-                        let type_obj = TypeDB::load_type(type_id)?;
-                        type_obj.is_always_cargo()
-                            || (stacked && type_obj.category != Category::Drone)
-                    };
+                    let type_obj = TypeDB::load_type(type_id)?;
+                    let is_drone = stacked && type_obj.category == Category::Drone;
+                    // Sections are high,med,low,rig,subsystem,drone, then cargo. A fit without cargo
+                    // ends with its drones, so a last section starting with drones is only cargo if
+                    // the fit already had a drone bay.
+                    let is_cargo = (section >= section_count
+                        && *last_is_cargo.get_or_insert(has_drone_bay || !is_drone))
+                        || type_obj.is_always_cargo()
+                        || (stacked && !is_drone);
+                    if is_drone && !is_cargo {
+                        has_drone_bay = true;
+                    }
 
                     let desto = if is_cargo {
                         &mut fit.cargo
@@ -173,14 +304,146 @@ impl Fitting {
 
                     *desto.entry(type_id).or_insert(0) += count;
                 }
-            } else {
-                return Err(FitError::ParseError);
+            } else if !line.is_empty() {
+                return Err(line_error(
+                    number,
+                    line,
+                    "missing a [Hull, Name] header before",
+                ));
             }
         }
 
         Ok(fittings)
     }
 
+    /// Sorts modules into their EFT sections: low, med, high, rig, subsystem and drones.
+    /// Items are sorted by name within a section.
+    fn sections(&self) -> Result<[Vec<(String, i64)>; 6], FitError> {
+        let mut ids: Vec<TypeID> = self.modules.keys().copied().collect();
+        ids.push(self.hull);
+        let names = TypeDB::names_of(&ids)?;
+
+        let mut sections: [Vec<(String, i64)>; 6] = Default::default();
+        for (&id, &count) in &self.modules {
+            let module = TypeDB::load_type(id)?;
+            let section = match module.slot() {
+                Some("low") => 0,
+                Some("med") => 1,
+                Some("high") => 2,
+                Some("rig") => 3,
+                Some("drone") => 5,
+                // Subsystems are the only fitted modules without a slot effect
+                _ => 4,
+            };
+            let name = names.get(&id).cloned().ok_or(FitError::InvalidModule)?;
+            sections[section].push((name, count));
+        }
+
+        for section in sections.iter_mut() {
+            section.sort();
+        }
+        Ok(sections)
+    }
+
+    fn cargo_names(&self) -> Result<Vec<(String, i64)>, FitError> {
+        let ids: Vec<TypeID> = self.cargo.keys().copied().collect();
+        let names = TypeDB::names_of(&ids)?;
+
+        let mut cargo = Vec::new();
+        for (id, &count) in &self.cargo {
+            cargo.push((names.get(id).cloned().ok_or(FitError::InvalidModule)?, count));
+        }
+        cargo.sort();
+        Ok(cargo)
+    }
+
+    pub fn to_eft(&self, name: &str) -> Result<String, FitError> {
+        let [low, med, high, rig, subsystem, drones] = self.sections()?;
+
+        let mut eft = format!("[{}, {}]\n", TypeDB::name_of(self.hull)?, name);
+        for section in [low, med, high, rig, subsystem] {
+            for (name, count) in section {
+                for _ in 0..count {
+                    eft += &format!("{}\n", name);
+                }
+            }
+            eft += "\n";
+        }
+
+        // Drones and cargo are stacked, each gets its own section
+        eft += "\n";
+        for (name, count) in drones {
+            eft += &format!("{} x{}\n", name, count);
+        }
+        eft += "\n";
+        for (name, count) in self.cargo_names()? {
+            eft += &format!("{} x{}\n", name, count);
+        }
+
+        Ok(eft)
+    }
+
+    /// Writes fits in the XML format used by the game client and pyfa
+    pub fn to_xml(fits: &[(&str, &Fitting)]) -> Result<String, FitError> {
+        fn escape(value: &str) -> String {
+            value
+                .replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;")
+                .replace('"', "&quot;")
+                .replace('\'', "&apos;")
+        }
+
+        let mut xml = String::from("<?xml version=\"1.0\" ?>\n<fittings>\n");
+        for (name, fit) in fits {
+            let [low, med, high, rig, subsystem, drones] = fit.sections()?;
+
+            xml += &format!("  <fitting name=\"{}\">\n", escape(name));
+            xml += "    <description value=\"\"/>\n";
+            xml += &format!(
+                "    <shipType value=\"{}\"/>\n",
+                escape(&TypeDB::name_of(fit.hull)?)
+            );
+            for (slot, section) in [
+                ("low", low),
+                ("med", med),
+                ("hi", high),
+                ("rig", rig),
+                ("subsystem", subsystem),
+            ] {
+                let mut index = 0;
+                for (name, count) in section {
+                    for _ in 0..count {
+                        xml += &format!(
+                            "    <hardware slot=\"{} slot {}\" type=\"{}\"/>\n",
+                            slot,
+                            index,
+                            escape(&name)
+                        );
+                        index += 1;
+                    }
+                }
+            }
+            for (name, count) in drones {
+                xml += &format!(
+                    "    <hardware qty=\"{}\" slot=\"drone bay\" type=\"{}\"/>\n",
+                    count,
+                    escape(&name)
+                );
+            }
+            for (name, count) in fit.cargo_names()? {
+                xml += &format!(
+                    "    <hardware qty=\"{}\" slot=\"cargo\" type=\"{}\"/>\n",
+                    count,
+                    escape(&name)
+                );
+            }
+            xml += "  </fitting>\n";
+        }
+
+        Ok(xml + "</fittings>\n")
+    }
+
     pub fn validate(&self) -> Result<(), FitError> {
         // Build a set of all IDs to minimize database usage
         let mut all_ids = BTreeSet::new();
@@ -231,7 +494,7 @@ impl Fitting {
 
 #[cfg(test)]
 mod tests {
-    use super::Fitting;
+    use super::{EftError, FitError, Fitting};
 
     #[test]
     fn test_parse_dna() {
@@ -295,6 +558,69 @@ Large Micro Jump Drive x1
         assert_eq!(parsed.to_dna().unwrap(), "17736:3057;4:4383_;1:12816;2::");
     }
 
+    #[test]
+    fn test_eft_round_trip() {
+        let fit = Fitting::from_dna("17736:3057;4:2456;2:4383_;1:12816;2::").unwrap();
+        let eft = fit.to_eft("Doctrine Nightmare").unwrap();
+        assert!(eft.starts_with("[Nightmare, Doctrine Nightmare]\n"));
+        assert!(eft.contains("Hobgoblin II x2\n\n"));
+
+        let parsed = Fitting::from_eft(&eft).unwrap().pop().expect("Parsed");
+        assert_eq!(parsed.hull, fit.hull);
+        assert_eq!(parsed.modules, fit.modules);
+        assert_eq!(parsed.cargo, fit.cargo);
+    }
+
+    #[test]
+    fn test_eft_round_trip_without_cargo() {
+        let fit = Fitting::from_dna("17736:3057;4:2456;2::").unwrap();
+        let eft = fit.to_eft("Doctrine Nightmare").unwrap();
+
+        let parsed = Fitting::from_eft(&eft).unwrap().pop().expect("Parsed");
+        assert_eq!(parsed.modules, fit.modules);
+        assert!(parsed.cargo.is_empty());
+    }
+
+    #[test]
+    fn test_parse_localized_eft() {
+        let parsed = Fitting::from_eft("[Kapsel, Test]\n").unwrap();
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].hull, 670);
+    }
+
+    #[test]
+    fn test_eft_errors() {
+        let error = match Fitting::from_eft("[Nightmare, X]\n\nMega Puls Laser II\n") {
+            Err(FitError::EftLine(error)) => error,
+            other => panic!("Unexpected result {:?}", other),
+        };
+        assert_eq!(error.line, 3);
+        assert_eq!(error.text, "Mega Puls Laser II");
+        assert_eq!(error.suggestions, vec!["Mega Pulse Laser II"]);
+
+        let error = match Fitting::from_eft("[Nightmare, X]\nConflagration L xtwo\n") {
+            Err(FitError::EftLine(error)) => error,
+            other => panic!("Unexpected result {:?}", other),
+        };
+        assert_eq!(error.line, 2);
+        assert_eq!(error.reason, "invalid item count");
+
+        assert!(matches!(
+            Fitting::from_eft("Mega Pulse Laser II\n"),
+            Err(FitError::EftLine(EftError { line: 1, .. }))
+        ));
+    }
+
+    #[test]
+    fn test_to_xml() {
+        let fit = Fitting::from_dna("17736:3057;2:12816;2::").unwrap();
+        let xml = Fitting::to_xml(&[("A & B", &fit)]).unwrap();
+        assert!(xml.contains("<fitting name=\"A &amp; B\">"));
+        assert!(xml.contains("<shipType value=\"Nightmare\"/>"));
+        assert!(xml.contains("<hardware slot=\"hi slot 1\" type=\"Mega Pulse Laser II\"/>"));
+        assert!(xml.contains("<hardware qty=\"2\" slot=\"cargo\" type=\"Conflagration L\"/>"));
+    }
+
     #[test]
     fn test_parse_eft_2() {
         let parsed = Fitting::from_eft(
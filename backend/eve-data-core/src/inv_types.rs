@@ -1,6 +1,6 @@
-use super::{Attribute, Category, Effect};
+use super::{Attribute, Category, Effect, Modifier};
 use rusqlite::OptionalExtension;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::iter;
 use std::sync::{Arc, RwLock};
 
@@ -21,6 +21,7 @@ pub enum TypeError {
 pub struct Type {
     pub id: TypeID,
     pub name: String,
+    pub group_id: i32,
     pub category: Category,
     pub attributes: HashMap<Attribute, f32>,
     pub effects: HashSet<Effect>,
@@ -50,6 +51,35 @@ impl Type {
     pub fn is_always_cargo(&self) -> bool {
         self.category == Category::Charge || self.category == Category::Implant
     }
+
+    pub fn is_weapon(&self) -> bool {
+        self.effects.contains(&Effect::turret_fitted())
+            || self.effects.contains(&Effect::launcher_fitted())
+    }
+
+    /// Whether `charge` can be loaded into this module
+    pub fn can_load(&self, charge: &Type) -> bool {
+        let fits_group = [
+            Attribute::ChargeGroup1,
+            Attribute::ChargeGroup2,
+            Attribute::ChargeGroup3,
+            Attribute::ChargeGroup4,
+            Attribute::ChargeGroup5,
+        ]
+        .iter()
+        .any(|a| self.attributes.get(a).map(|&g| g as i32) == Some(charge.group_id));
+        if !fits_group {
+            return false;
+        }
+
+        match (
+            self.attributes.get(&Attribute::ChargeSize),
+            charge.attributes.get(&Attribute::ChargeSize),
+        ) {
+            (Some(size), Some(charge_size)) => size == charge_size,
+            _ => true,
+        }
+    }
 }
 
 std::thread_local!(static CONN: rusqlite::Connection = rusqlite::Connection::open("sqlite-shrunk.sqlite").unwrap());
@@ -73,6 +103,7 @@ impl TypeDB {
         struct BasicData {
             id: TypeID,
             name: String,
+            group_id: i32,
             category: Category,
         }
 
@@ -81,6 +112,7 @@ impl TypeDB {
                 SELECT
                     typeID,
                     typeName,
+                    groupID,
                     (SELECT categoryID FROM invGroups WHERE invGroups.groupID = invTypes.groupID) categoryID
                 FROM invTypes
                 WHERE typeID IN ({})
@@ -92,7 +124,8 @@ impl TypeDB {
                 Ok(BasicData {
                     id: row.get(0)?,
                     name: row.get(1)?,
-                    category: Category::from_id(row.get(2)?),
+                    group_id: row.get(2)?,
+                    category: Category::from_id(row.get(3)?),
                 })
             })?;
             let mut basic = HashMap::new();
@@ -207,6 +240,7 @@ impl TypeDB {
                     Some(Type {
                         id,
                         name: basic.name,
+                        group_id: basic.group_id,
                         category: basic.category,
                         attributes: attrs,
                         effects: effects.remove(&id).unwrap(),
@@ -346,6 +380,88 @@ impl TypeDB {
         Ok(result)
     }
 
+    /// Like `ids_of`, but matches the type names of a client language such as "de" or "ru"
+    pub fn ids_of_localized<'a>(
+        names: &[&'a str],
+        language: &str,
+    ) -> Result<HashMap<&'a str, TypeID>, TypeError> {
+        if language == "en" {
+            return Self::ids_of(names);
+        }
+
+        let unique: HashSet<&str> = names.iter().copied().collect();
+        if unique.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let from_db = CONN.with(|conn| -> Result<_, TypeError> {
+            let placeholders = vec!["?"; unique.len()].join(",");
+            let query = format!(
+                "
+                SELECT invTypeNames.typeID, invTypeNames.typeName
+                FROM invTypeNames JOIN invTypes ON invTypes.typeID = invTypeNames.typeID
+                WHERE language = ? AND invTypeNames.typeName IN ({})
+                ORDER BY published ASC
+            ",
+                placeholders
+            );
+
+            let mut prepared = conn.prepare(&query)?;
+            let params = iter::once(language).chain(unique.iter().copied());
+            let rows = prepared.query_map(rusqlite::params_from_iter(params), |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })?;
+
+            let mut result: HashMap<String, TypeID> = HashMap::new();
+            for row in rows {
+                let (id, name) = row?;
+                result.insert(name, id);
+            }
+
+            Ok(result)
+        })?;
+
+        let mut result = HashMap::new();
+        for name in unique {
+            if let Some(type_id) = from_db.get(name) {
+                result.insert(name, *type_id);
+            }
+        }
+        Ok(result)
+    }
+
+    /// Guesses the client language a list of type names comes from: the language matching the
+    /// most names, preferring English on a tie
+    pub fn detect_language(names: &[&str]) -> Result<Option<String>, TypeError> {
+        let unique: HashSet<&str> = names.iter().copied().collect();
+        if unique.is_empty() {
+            return Ok(None);
+        }
+
+        let counts = CONN.with(|conn| -> Result<_, TypeError> {
+            let placeholders = vec!["?"; unique.len()].join(",");
+            let query = format!(
+                "
+                SELECT language, COUNT(DISTINCT typeName) FROM invTypeNames
+                WHERE typeName IN ({})
+                GROUP BY language
+            ",
+                placeholders
+            );
+
+            let mut prepared = conn.prepare(&query)?;
+            let rows = prepared.query_map(rusqlite::params_from_iter(unique.iter()), |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })?;
+            Ok(rows.collect::<Result<Vec<(String, i64)>, _>>()?)
+        })?;
+
+        Ok(counts
+            .into_iter()
+            .max_by_key(|(language, count)| (*count, language == "en"))
+            .map(|(language, _)| language))
+    }
+
     pub fn name_of_system(id: i64) -> Result<String, TypeError> {
         Ok(CONN.with(|conn| {
             conn.query_row("SELECT name FROM systems WHERE id=?", [id], |row| row.get(0))
@@ -392,6 +508,63 @@ impl TypeDB {
         })?)
     }
 
+    /// Tiers of every module and drone family, as precomputed by sde_import
+    pub fn variation_families() -> Result<Vec<HashMap<TypeID, i64>>, TypeError> {
+        let mut families: BTreeMap<TypeID, HashMap<TypeID, i64>> = BTreeMap::new();
+        CONN.with(|conn| -> Result<_, rusqlite::Error> {
+            let mut prepared =
+                conn.prepare("SELECT parentTypeID, typeID, tier FROM variationFamilies")?;
+            let mut rows = prepared.query([])?;
+            while let Some(row) = rows.next()? {
+                families
+                    .entry(row.get(0)?)
+                    .or_default()
+                    .insert(row.get(1)?, row.get(2)?);
+            }
+            Ok(())
+        })?;
+        Ok(families.into_values().collect())
+    }
+
+    /// Modifiers of the given effects, as kept by sde_import
+    pub fn effect_modifiers(
+        effects: &[Effect],
+    ) -> Result<HashMap<Effect, Vec<Modifier>>, TypeError> {
+        let mut result: HashMap<Effect, Vec<Modifier>> = HashMap::new();
+        if effects.is_empty() {
+            return Ok(result);
+        }
+
+        CONN.with(|conn| -> Result<_, rusqlite::Error> {
+            let placeholders = vec!["?"; effects.len()].join(",");
+            let query = format!(
+                "
+                SELECT effectID, func, domain, modifiedAttributeID, modifyingAttributeID, operation, skillTypeID, groupID
+                FROM dgmEffectModifiers WHERE effectID IN ({})
+            ",
+                placeholders
+            );
+
+            let mut prepared = conn.prepare(&query)?;
+            let ids = effects.iter().map(|effect| effect.0);
+            let mut rows = prepared.query(rusqlite::params_from_iter(ids))?;
+            while let Some(row) = rows.next()? {
+                result.entry(Effect(row.get(0)?)).or_default().push(Modifier {
+                    func: row.get(1)?,
+                    domain: row.get(2)?,
+                    modified: Attribute::from_id(row.get(3)?),
+                    modifying: Attribute::from_id(row.get(4)?),
+                    operation: row.get(5)?,
+                    skill: row.get(6)?,
+                    group: row.get(7)?,
+                });
+            }
+            Ok(())
+        })?;
+
+        Ok(result)
+    }
+
     pub fn type_variations(id: TypeID) -> Result<HashMap<TypeID, i64>, TypeError> {
         let parent_type_id =
             CONN.with(|conn| -> Result<Option<Option<TypeID>>, rusqlite::Error> {
@@ -502,6 +675,19 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_localized_names() {
+        assert_eq!(
+            TypeDB::detect_language(&["Capsule"]).unwrap().as_deref(),
+            Some("en")
+        );
+        assert_eq!(TypeDB::detect_language(&["TDF Titan"]).unwrap(), None);
+
+        let ids = TypeDB::ids_of_localized(&["Kapsel", "Capsule"], "de").unwrap();
+        assert_eq!(ids.get("Kapsel"), Some(&670));
+        assert!(!ids.contains_key("Capsule"));
+    }
+
     #[test]
     fn test_requirements() {
         let t = TypeDB::load_type(id_of("Monitor")).unwrap();
@@ -533,4 +719,22 @@ mod tests {
             Some(&16)
         );
     }
+
+    #[test]
+    fn test_variation_families() {
+        let families = TypeDB::variation_families().unwrap();
+        let mwd = families
+            .iter()
+            .find(|family| family.contains_key(&id_of("500MN Microwarpdrive I")))
+            .expect("Missing the 500MN Microwarpdrive family");
+        assert_eq!(
+            mwd,
+            &TypeDB::type_variations(id_of("500MN Microwarpdrive I")).unwrap()
+        );
+
+        // Only modules and drones
+        assert!(!families
+            .iter()
+            .any(|family| family.contains_key(&id_of("Nightmare"))));
+    }
 }
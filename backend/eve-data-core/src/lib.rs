@@ -1,11 +1,15 @@
 mod attribute;
 mod category;
+mod dogma;
 mod effect;
 mod fitting;
 mod inv_types;
+mod stats;
 
 pub use attribute::Attribute;
 pub use category::Category;
-pub use effect::Effect;
-pub use fitting::{FitError, Fitting};
+pub use dogma::{FitProblem, FittingStats, Usage};
+pub use effect::{Effect, Modifier};
+pub use fitting::{EftError, FitError, Fitting};
 pub use inv_types::{SkillLevel, Type, TypeDB, TypeError, TypeID};
+pub use stats::{FitStats, Resists};
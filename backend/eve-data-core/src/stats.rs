@@ -0,0 +1,428 @@
+use std::collections::HashMap;
+
+use super::dogma::{attribute, skill_bonus};
+use super::{Attribute, Category, Effect, FitError, Fitting, SkillLevel, Type, TypeDB, TypeID};
+
+// Skills used by the calculation, as (skill, percentage per level)
+const HULL_UPGRADES: (TypeID, f64) = (3394, 5.0);
+const SHIELD_MANAGEMENT: (TypeID, f64) = (3419, 5.0);
+const MECHANICS: (TypeID, f64) = (3392, 5.0);
+const SURGICAL_STRIKE: (TypeID, f64) = (3315, 3.0);
+const GUNNERY: (TypeID, f64) = (3300, -2.0);
+const RAPID_FIRING: (TypeID, f64) = (3310, -4.0);
+const WARHEAD_UPGRADES: (TypeID, f64) = (20315, 2.0);
+const RAPID_LAUNCH: (TypeID, f64) = (21071, -3.0);
+const DRONE_INTERFACING: (TypeID, f64) = (3442, 10.0);
+const CAPACITOR_MANAGEMENT: (TypeID, f64) = (3418, 5.0);
+const CAPACITOR_SYSTEMS_OPERATION: (TypeID, f64) = (3417, -5.0);
+const NAVIGATION: (TypeID, f64) = (3449, 5.0);
+const ACCELERATION_CONTROL: (TypeID, f64) = (3452, 5.0);
+
+const MAX_DRONES: i64 = 5;
+
+/// Shield hardeners, shield resistance amplifiers and shield rigs, other resist modules are armor
+const SHIELD_RESIST_GROUPS: [i32; 3] = [77, 295, 774];
+
+/// Resonances in EM, explosive, kinetic, thermal order
+const SHIELD_RESONANCES: [Attribute; 4] = [
+    Attribute::ShieldEmResonance,
+    Attribute::ShieldExplosiveResonance,
+    Attribute::ShieldKineticResonance,
+    Attribute::ShieldThermalResonance,
+];
+const ARMOR_RESONANCES: [Attribute; 4] = [
+    Attribute::ArmorEmResonance,
+    Attribute::ArmorExplosiveResonance,
+    Attribute::ArmorKineticResonance,
+    Attribute::ArmorThermalResonance,
+];
+const HULL_RESONANCES: [Attribute; 4] = [
+    Attribute::HullEmResonance,
+    Attribute::HullExplosiveResonance,
+    Attribute::HullKineticResonance,
+    Attribute::HullThermalResonance,
+];
+const RESIST_BONUSES: [Attribute; 4] = [
+    Attribute::EmResist,
+    Attribute::ExplosiveResist,
+    Attribute::KineticResist,
+    Attribute::ThermalResist,
+];
+const DAMAGE: [Attribute; 4] = [
+    Attribute::EmDamage,
+    Attribute::ExplosiveDamage,
+    Attribute::KineticDamage,
+    Attribute::ThermalDamage,
+];
+
+/// Resists per tank layer, in EM, explosive, kinetic, thermal order. 0.0 is no resist.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Resists {
+    pub shield: [f64; 4],
+    pub armor: [f64; 4],
+    pub hull: [f64; 4],
+}
+
+/// Combat stats of a fit.
+///
+/// Pilot skills, fitted modules and loaded charges are applied. Hull bonuses, implants, boosts
+/// and heat are not, so compare stats between fits of the same hull rather than trusting the
+/// absolute numbers.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FitStats {
+    pub resists: Resists,
+    /// Effective HP against an even damage spread
+    pub ehp: f64,
+    /// Sustained DPS of turrets, launchers and drones with the best charges in cargo
+    pub dps: f64,
+    /// Capacitor level the fit settles at with everything running, if it is cap stable
+    pub cap_stable_at: Option<f64>,
+    /// Seconds until the capacitor runs dry if it is not cap stable
+    pub cap_lasts: Option<f64>,
+    /// Top speed with the propulsion module running
+    pub speed: f64,
+}
+
+/// Applies stacking penalized multipliers, strongest first
+fn stacked(base: f64, mut multipliers: Vec<f64>) -> f64 {
+    multipliers.sort_by(|a, b| (b - 1.0).abs().partial_cmp(&(a - 1.0).abs()).unwrap());
+    multipliers
+        .into_iter()
+        .enumerate()
+        .fold(base, |value, (i, multiplier)| {
+            let penalty = (-(i as f64 / 2.67).powi(2)).exp();
+            value * (1.0 + (multiplier - 1.0) * penalty)
+        })
+}
+
+fn ehp(hp: f64, resonances: &[f64; 4]) -> f64 {
+    let average: f64 = resonances.iter().sum::<f64>() / 4.0;
+    if average > 0.0 {
+        hp / average
+    } else {
+        0.0
+    }
+}
+
+fn volley(the_type: &Type) -> f64 {
+    DAMAGE.iter().map(|&a| attribute(the_type, a)).sum()
+}
+
+/// Capacitor level the fit settles at, or how many seconds it lasts
+fn capacitor(capacity: f64, recharge_ms: f64, usage: f64) -> (Option<f64>, Option<f64>) {
+    if capacity <= 0.0 || recharge_ms <= 0.0 {
+        return (None, None);
+    }
+    let recharge = recharge_ms / 1000.0;
+    let peak = 2.5 * capacity / recharge;
+    if usage <= peak {
+        // Solve sqrt(x) - x = usage * recharge / (10 * capacity) for the stable side of the curve
+        let c = usage * recharge / (10.0 * capacity);
+        let root = (1.0 + (1.0 - 4.0 * c).max(0.0).sqrt()) / 2.0;
+        return (Some(root * root), None);
+    }
+
+    let mut level = capacity;
+    let mut seconds = 0.0;
+    while level > 0.0 && seconds < 86400.0 {
+        let x = level / capacity;
+        level += (10.0 * capacity / recharge * (x.sqrt() - x) - usage) * 0.1;
+        seconds += 0.1;
+    }
+    (None, Some(seconds))
+}
+
+impl FitStats {
+    pub fn calculate(
+        fit: &Fitting,
+        skills: &HashMap<TypeID, SkillLevel>,
+    ) -> Result<FitStats, FitError> {
+        let hull = TypeDB::load_type(fit.hull)?;
+
+        let mut ids: Vec<TypeID> = fit.modules.keys().copied().collect();
+        ids.extend(fit.cargo.keys().copied());
+        let types = TypeDB::load_types(&ids)?;
+        let the_type = |id: &TypeID| types[id].as_ref().ok_or(FitError::InvalidModule);
+
+        let charges = fit
+            .cargo
+            .keys()
+            .map(the_type)
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .filter(|t| t.category == Category::Charge && volley(t) > 0.0)
+            .collect::<Vec<_>>();
+
+        let mut shield_hp = attribute(&hull, Attribute::ShieldHp);
+        let mut armor_hp = attribute(&hull, Attribute::ArmorHp);
+        let mut hull_hp = attribute(&hull, Attribute::StructureHp);
+        let mut hp_multipliers = [
+            skill_bonus(skills, SHIELD_MANAGEMENT),
+            skill_bonus(skills, HULL_UPGRADES),
+            skill_bonus(skills, MECHANICS),
+        ];
+
+        let mut resonances = [[0.0; 4]; 3];
+        let mut resist_bonuses: [Vec<Vec<f64>>; 3] = Default::default();
+        for (layer, attributes) in [SHIELD_RESONANCES, ARMOR_RESONANCES, HULL_RESONANCES]
+            .iter()
+            .enumerate()
+        {
+            for (i, &a) in attributes.iter().enumerate() {
+                resonances[layer][i] = hull.attributes.get(&a).copied().unwrap_or(1.0) as f64;
+            }
+            resist_bonuses[layer] = vec![Vec::new(); 4];
+        }
+
+        let mut turret_damage = Vec::new();
+        let mut missile_damage = Vec::new();
+        let mut weapon_rof = Vec::new();
+        let mut weapons = Vec::new();
+
+        let mut cap_capacity = attribute(&hull, Attribute::CapacitorCapacity);
+        let mut cap_recharge = attribute(&hull, Attribute::CapacitorRechargeRate)
+            * skill_bonus(skills, CAPACITOR_SYSTEMS_OPERATION);
+        let mut cap_multiplier = skill_bonus(skills, CAPACITOR_MANAGEMENT);
+        let mut cap_usage = 0.0;
+
+        let mut speed_boost: f64 = 0.0;
+        let mut drones = Vec::new();
+
+        for (id, &count) in &fit.modules {
+            let module = the_type(id)?;
+
+            if module.slot() == Some("drone") {
+                drones.push((module, count));
+                continue;
+            }
+
+            // Resist bonuses don't say which layer they apply to, the module group does
+            let layer = match SHIELD_RESIST_GROUPS.contains(&module.group_id) {
+                true => 0,
+                false => 1,
+            };
+
+            for _ in 0..count {
+                shield_hp += attribute(module, Attribute::ShieldCapacityBonus);
+                armor_hp += attribute(module, Attribute::ArmorHpBonusAdd);
+                for (i, a) in [
+                    Attribute::ShieldCapacityMultiplier,
+                    Attribute::ArmorHpMultiplier,
+                    Attribute::StructureHpMultiplier,
+                ]
+                .iter()
+                .enumerate()
+                {
+                    if let Some(&multiplier) = module.attributes.get(a) {
+                        hp_multipliers[i] *= multiplier as f64;
+                    }
+                }
+
+                // Damage controls carry resonances for every layer, hull resists aren't penalized
+                for (l, attributes) in [SHIELD_RESONANCES, ARMOR_RESONANCES]
+                    .iter()
+                    .enumerate()
+                {
+                    for (i, a) in attributes.iter().enumerate() {
+                        if let Some(&resonance) = module.attributes.get(a) {
+                            resist_bonuses[l][i].push(resonance as f64);
+                        }
+                    }
+                }
+                for (i, a) in HULL_RESONANCES.iter().enumerate() {
+                    if let Some(&resonance) = module.attributes.get(a) {
+                        resonances[2][i] *= resonance as f64;
+                    }
+                }
+                for (i, a) in RESIST_BONUSES.iter().enumerate() {
+                    if let Some(&bonus) = module.attributes.get(a) {
+                        resist_bonuses[layer][i].push(1.0 + bonus as f64 / 100.0);
+                    }
+                }
+
+                if module.is_weapon() {
+                    weapons.push(module);
+                } else {
+                    if let Some(&multiplier) = module.attributes.get(&Attribute::DamageMultiplier) {
+                        turret_damage.push(multiplier as f64);
+                    }
+                    if let Some(&multiplier) =
+                        module.attributes.get(&Attribute::MissileDamageMultiplier)
+                    {
+                        missile_damage.push(multiplier as f64);
+                    }
+                    if let Some(&multiplier) =
+                        module.attributes.get(&Attribute::RateOfFireMultiplier)
+                    {
+                        weapon_rof.push(multiplier as f64);
+                    }
+                }
+
+                cap_capacity += attribute(module, Attribute::CapacitorBonus);
+                if let Some(&multiplier) = module
+                    .attributes
+                    .get(&Attribute::CapacitorCapacityMultiplier)
+                {
+                    cap_multiplier *= multiplier as f64;
+                }
+                if let Some(&multiplier) = module
+                    .attributes
+                    .get(&Attribute::CapacitorRechargeRateMultiplier)
+                {
+                    cap_recharge *= multiplier as f64;
+                }
+                let cycle = match module.attributes.get(&Attribute::Duration) {
+                    Some(&duration) => duration as f64,
+                    None => attribute(module, Attribute::RateOfFire),
+                };
+                if cycle > 0.0 {
+                    cap_usage += attribute(module, Attribute::CapacitorNeed) / (cycle / 1000.0);
+                }
+
+                let mass =
+                    attribute(&hull, Attribute::Mass) + attribute(module, Attribute::MassAddition);
+                if mass > 0.0 {
+                    let boost = attribute(module, Attribute::SpeedFactor)
+                        * skill_bonus(skills, ACCELERATION_CONTROL)
+                        / 100.0
+                        * attribute(module, Attribute::SpeedBoostFactor)
+                        / mass;
+                    speed_boost = speed_boost.max(boost);
+                }
+            }
+        }
+
+        let mut resists = [[0.0; 4]; 3];
+        for layer in 0..3 {
+            for i in 0..4 {
+                resonances[layer][i] =
+                    stacked(resonances[layer][i], resist_bonuses[layer][i].clone());
+                resists[layer][i] = 1.0 - resonances[layer][i];
+            }
+        }
+
+        shield_hp *= hp_multipliers[0];
+        armor_hp *= hp_multipliers[1];
+        hull_hp *= hp_multipliers[2];
+
+        let turret_damage = stacked(skill_bonus(skills, SURGICAL_STRIKE), turret_damage);
+        let missile_damage = stacked(skill_bonus(skills, WARHEAD_UPGRADES), missile_damage);
+        let weapon_rof = stacked(1.0, weapon_rof);
+        let turret_rof = skill_bonus(skills, GUNNERY) * skill_bonus(skills, RAPID_FIRING);
+        let missile_rof = skill_bonus(skills, RAPID_LAUNCH);
+
+        let mut dps = 0.0;
+        for weapon in weapons {
+            let best_charge = charges
+                .iter()
+                .filter(|charge| weapon.can_load(charge))
+                .map(|charge| volley(charge))
+                .fold(0.0, f64::max);
+            let rof = attribute(weapon, Attribute::RateOfFire) / 1000.0 * weapon_rof;
+
+            let (damage, rof) = match weapon.effects.contains(&Effect::turret_fitted()) {
+                true => (
+                    best_charge * attribute(weapon, Attribute::DamageMultiplier) * turret_damage,
+                    rof * turret_rof,
+                ),
+                false => (best_charge * missile_damage, rof * missile_rof),
+            };
+            if rof > 0.0 {
+                dps += damage / rof;
+            }
+        }
+
+        let mut drone_dps: Vec<(f64, i64)> = drones
+            .into_iter()
+            .map(|(drone, count)| {
+                let rof = attribute(drone, Attribute::RateOfFire) / 1000.0;
+                let damage = volley(drone)
+                    * attribute(drone, Attribute::DamageMultiplier)
+                    * skill_bonus(skills, DRONE_INTERFACING);
+                (if rof > 0.0 { damage / rof } else { 0.0 }, count)
+            })
+            .collect();
+        drone_dps.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap());
+        let mut drones_left = MAX_DRONES;
+        for (drone_dps, count) in drone_dps {
+            let launched = count.min(drones_left);
+            dps += drone_dps * launched as f64;
+            drones_left -= launched;
+        }
+
+        let (cap_stable_at, cap_lasts) =
+            capacitor(cap_capacity * cap_multiplier, cap_recharge, cap_usage);
+
+        let speed = attribute(&hull, Attribute::MaxVelocity)
+            * skill_bonus(skills, NAVIGATION)
+            * (1.0 + speed_boost);
+
+        Ok(FitStats {
+            resists: Resists {
+                shield: resists[0],
+                armor: resists[1],
+                hull: resists[2],
+            },
+            ehp: ehp(shield_hp, &resonances[0])
+                + ehp(armor_hp, &resonances[1])
+                + ehp(hull_hp, &resonances[2]),
+            dps,
+            cap_stable_at,
+            cap_lasts,
+            speed,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{capacitor, stacked, FitStats, HashMap};
+    use crate::Fitting;
+
+    #[test]
+    fn test_stacking() {
+        assert_eq!(stacked(1.0, vec![]), 1.0);
+        let penalized = stacked(1.0, vec![0.8, 0.8]);
+        assert!(penalized > 0.64 && penalized < 0.7);
+    }
+
+    #[test]
+    fn test_capacitor() {
+        // Peak recharge of 1000 GJ over 100s is 25 GJ/s
+        let (stable, _) = capacitor(1000.0, 100000.0, 20.0);
+        assert!(stable.unwrap() > 0.25);
+        let (stable, lasts) = capacitor(1000.0, 100000.0, 30.0);
+        assert!(stable.is_none());
+        assert!(lasts.unwrap() > 0.0);
+    }
+
+    #[test]
+    fn test_fit_stats() {
+        let fit = Fitting::from_dna("17736:3057;4:2456;2:12816;2::").unwrap();
+        let stats = FitStats::calculate(&fit, &HashMap::new()).unwrap();
+
+        assert!(stats.ehp > 0.0);
+        assert!(stats.dps > 0.0);
+        assert!(stats.speed > 0.0);
+    }
+
+    #[test]
+    fn test_resist_layers() {
+        let stats = |eft: &str| {
+            let fit = Fitting::from_eft(eft).unwrap().pop().unwrap();
+            FitStats::calculate(&fit, &HashMap::new()).unwrap().resists
+        };
+        let base = stats("[Rokh, Base]\n");
+        let hardened = stats("[Rokh, Hardened]\nMultispectrum Shield Hardener II\n");
+        assert!(hardened.shield[0] > base.shield[0]);
+        assert_eq!(hardened.armor, base.armor);
+
+        // Hull resonances multiply without a stacking penalty
+        let one = stats("[Rokh, DC]\nDamage Control II\n");
+        let two = stats("[Rokh, DC]\nDamage Control II\nDamage Control II\n");
+        let resonance = (1.0 - one.hull[0]) / (1.0 - base.hull[0]);
+        let expected = 1.0 - (1.0 - base.hull[0]) * resonance * resonance;
+        assert!(resonance < 1.0);
+        assert!((two.hull[0] - expected).abs() < 1e-6);
+    }
+}
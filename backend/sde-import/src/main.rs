@@ -0,0 +1,509 @@
+//! Builds the `sqlite-shrunk.sqlite` used by `eve_data_core` from a local copy of the SDE.
+//!
+//! The source is either the SQLite conversion of the SDE (`sqlite-latest.sqlite`, optionally
+//! still compressed as `.bz2`) or a directory with the extracted YAML SDE. Only the tables and
+//! rows `TypeDB` reads are kept, plus the type names in every client language. When a previous
+//! database exists, types that were renamed, unpublished or removed are reported, since those
+//! break `type_id!` and the data files.
+//!
+//! Usage: sde_import <source> [--output sqlite-shrunk.sqlite] [--report report.txt]
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File};
+use std::io;
+use std::path::Path;
+
+use rusqlite::{params, Connection};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+const ATTRIBUTES: &[i32] = &[
+    275, // skill training multiplier
+    633, // meta level
+    984, 985, 986, 987, // resists
+    182, 183, 184, 1285, 1289, 1290, // skill req
+    277, 278, 279, 1286, 1287, 1288, // skill req level
+    12, 13, 14, 1137, 101, 102, // slots and hardpoints
+    11, 48, 1132, // powergrid, cpu and calibration output
+    30, 50, 1153, // powergrid, cpu and calibration need
+    145, 202, 313, 424, 549, // fitting modules and implants
+    1368, 1369, 1374, 1375, 1376, // subsystem slot modifiers
+    9, 265, 263, 72, 146, 148, 150, 1159, // hitpoints
+    109, 110, 111, 113, 267, 268, 269, 270, 271, 272, 273, 274, // resonances
+    51, 64, 114, 116, 117, 118, 128, 204, 213, 604, 605, 606, 609, 610, // damage and charges
+    6, 55, 67, 73, 144, 147, 482, // capacitor
+    4, 20, 37, 567, 796, // speed
+];
+
+const EFFECTS: &[i32] = &[11, 12, 13, 40, 42, 2663, 3772];
+
+// Slots, hardpoints and fitting resources. Effects modifying these (hull and role bonuses,
+// fitting skills) are kept with their modifiers, along with the attributes they modify by.
+const FITTING_ATTRIBUTES: &[i32] = &[14, 13, 12, 1137, 102, 101, 48, 11, 1132, 30, 50, 1153];
+
+// Client languages kept for localized type names
+const LANGUAGES: &[&str] = &["de", "en", "es", "fr", "ja", "ko", "ru", "zh"];
+
+const SCHEMA: &str = "
+    CREATE TABLE invTypes (typeID INTEGER NOT NULL, groupID INTEGER, typeName TEXT NOT NULL, published INTEGER NOT NULL);
+    CREATE TABLE invGroups (groupID INTEGER NOT NULL, categoryID INTEGER NOT NULL);
+    CREATE TABLE invMetaTypes (typeID INTEGER NOT NULL, parentTypeID INTEGER, metaGroupID INTEGER NOT NULL);
+    CREATE TABLE dgmTypeAttributes (typeID INTEGER NOT NULL, attributeID INTEGER NOT NULL, valueInt INTEGER, valueFloat REAL);
+    CREATE TABLE dgmTypeEffects (typeID INTEGER NOT NULL, effectID INTEGER NOT NULL);
+    CREATE TABLE systems (id INTEGER NOT NULL, name TEXT NOT NULL);
+    CREATE TABLE invTypeNames (typeID INTEGER NOT NULL, language TEXT NOT NULL, typeName TEXT NOT NULL);
+    CREATE TABLE variationFamilies (parentTypeID INTEGER NOT NULL, typeID INTEGER NOT NULL, tier INTEGER NOT NULL);
+    CREATE TABLE dgmEffectModifiers (effectID INTEGER NOT NULL, func TEXT NOT NULL, domain TEXT, modifiedAttributeID INTEGER NOT NULL, modifyingAttributeID INTEGER NOT NULL, operation INTEGER NOT NULL, skillTypeID INTEGER, groupID INTEGER);
+";
+
+const INDEXES: &str = "
+    CREATE INDEX invTypes_name ON invTypes (typeName);
+    CREATE INDEX invTypes_typeID ON invTypes (typeID);
+    CREATE INDEX invGroups_groupID ON invGroups (groupID);
+    CREATE INDEX invMetaTypes_typeID ON invMetaTypes (typeID);
+    CREATE INDEX invMetaTypes_parentTypeID ON invMetaTypes (parentTypeID);
+    CREATE INDEX dgmTypeAttributes_typeID ON dgmTypeAttributes (typeID);
+    CREATE INDEX dgmTypeEffects_typeID ON dgmTypeEffects (typeID);
+    CREATE INDEX invTypeNames_name ON invTypeNames (typeName);
+    CREATE INDEX dgmEffectModifiers_effectID ON dgmEffectModifiers (effectID);
+";
+
+// Module and drone families with their tiers, so the waitlist doesn't have to walk every
+// variation parent on startup. The parent is tier 0, variations use their meta level, or 1 for
+// T1 and 2 for T2 when they have none.
+const FAMILIES: &str = "
+    INSERT INTO variationFamilies
+    WITH members AS (
+        SELECT m.parentTypeID AS parent, m.typeID AS typeID, COALESCE(
+            CAST(COALESCE(a.valueInt, a.valueFloat) AS INTEGER),
+            CASE m.metaGroupID WHEN 1 THEN 1 WHEN 2 THEN 2 END
+        ) AS tier
+        FROM invMetaTypes m
+        LEFT JOIN dgmTypeAttributes a ON a.typeID = m.typeID AND a.attributeID = 633
+        WHERE m.parentTypeID IS NOT NULL
+    ), family AS (
+        SELECT parent, typeID, tier FROM members WHERE tier IS NOT NULL
+        UNION
+        SELECT parent, parent, 0 FROM members m WHERE NOT EXISTS (
+            SELECT 1 FROM members p WHERE p.parent = m.parent AND p.typeID = m.parent AND p.tier IS NOT NULL
+        )
+    )
+    SELECT parent, typeID, tier FROM family WHERE parent IN (
+        SELECT f.parent FROM family f
+        LEFT JOIN invTypes t ON t.typeID = f.typeID
+        LEFT JOIN invGroups g ON g.groupID = t.groupID
+        GROUP BY f.parent
+        HAVING COUNT(*) > 1 AND SUM(COALESCE(g.categoryID, 7) NOT IN (7, 18)) = 0
+    );
+";
+
+#[derive(Debug)]
+enum Change {
+    Renamed { id: i64, from: String, to: String },
+    Unpublished { id: i64, name: String },
+    Removed { id: i64, name: String },
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::Renamed { id, from, to } => write!(f, "renamed     {}: {} -> {}", id, from, to),
+            Change::Unpublished { id, name } => write!(f, "unpublished {}: {}", id, name),
+            Change::Removed { id, name } => write!(f, "removed     {}: {}", id, name),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct YamlType {
+    #[serde(rename = "groupID")]
+    group_id: i64,
+    #[serde(default)]
+    name: BTreeMap<String, String>,
+    #[serde(default)]
+    published: bool,
+    #[serde(rename = "metaGroupID")]
+    meta_group_id: Option<i64>,
+    #[serde(rename = "variationParentTypeID")]
+    parent_type_id: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct YamlGroup {
+    #[serde(rename = "categoryID")]
+    category_id: i64,
+}
+
+#[derive(Deserialize)]
+struct YamlDogmaAttribute {
+    #[serde(rename = "attributeID")]
+    attribute_id: i32,
+    value: f64,
+}
+
+#[derive(Deserialize)]
+struct YamlDogmaEffect {
+    #[serde(rename = "effectID")]
+    effect_id: i32,
+}
+
+#[derive(Deserialize)]
+struct YamlDogma {
+    #[serde(rename = "dogmaAttributes", default)]
+    attributes: Vec<YamlDogmaAttribute>,
+    #[serde(rename = "dogmaEffects", default)]
+    effects: Vec<YamlDogmaEffect>,
+}
+
+#[derive(Deserialize)]
+struct YamlModifier {
+    func: String,
+    domain: Option<String>,
+    #[serde(rename = "modifiedAttributeID")]
+    modified_attribute_id: Option<i32>,
+    #[serde(rename = "modifyingAttributeID")]
+    modifying_attribute_id: Option<i32>,
+    operation: Option<i32>,
+    #[serde(rename = "skillTypeID")]
+    skill_type_id: Option<i64>,
+    #[serde(rename = "groupID")]
+    group_id: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct YamlEffect {
+    #[serde(rename = "modifierInfo", default)]
+    modifiers: Vec<YamlModifier>,
+}
+
+#[derive(Deserialize)]
+struct YamlName {
+    #[serde(rename = "itemID")]
+    item_id: i64,
+    #[serde(rename = "itemName")]
+    item_name: String,
+}
+
+fn id_list(ids: &[i32]) -> String {
+    ids.iter()
+        .map(|id| id.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn read_yaml<T: DeserializeOwned>(dir: &Path, names: &[&str]) -> Result<T, Box<dyn Error>> {
+    for name in names {
+        let path = dir.join(name);
+        if path.exists() {
+            println!("Reading {}", path.display());
+            return Ok(serde_yaml::from_reader(File::open(path)?)?);
+        }
+    }
+    Err(format!("None of {:?} found in {}", names, dir.display()).into())
+}
+
+/// Keeps the modifiers that change fitting attributes. Returns the effects they belong to and the
+/// attributes they modify by, so those are imported as well.
+fn import_modifiers(
+    conn: &Connection,
+    effects: &BTreeMap<i64, YamlEffect>,
+) -> Result<(Vec<i32>, Vec<i32>), Box<dyn Error>> {
+    let mut effect_ids = EFFECTS.iter().copied().collect::<BTreeSet<i32>>();
+    let mut attribute_ids = ATTRIBUTES.iter().copied().collect::<BTreeSet<i32>>();
+
+    let tx = conn.unchecked_transaction()?;
+    for (&effect_id, effect) in effects {
+        for modifier in &effect.modifiers {
+            let (modified, modifying, operation) = match (
+                modifier.modified_attribute_id,
+                modifier.modifying_attribute_id,
+                modifier.operation,
+            ) {
+                (Some(modified), Some(modifying), Some(operation))
+                    if FITTING_ATTRIBUTES.contains(&modified) =>
+                {
+                    (modified, modifying, operation)
+                }
+                _ => continue,
+            };
+
+            tx.execute(
+                "INSERT INTO dgmEffectModifiers VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+                params![
+                    effect_id,
+                    modifier.func,
+                    modifier.domain,
+                    modified,
+                    modifying,
+                    operation,
+                    modifier.skill_type_id,
+                    modifier.group_id
+                ],
+            )?;
+            effect_ids.insert(effect_id as i32);
+            attribute_ids.insert(modifying);
+        }
+    }
+    tx.commit()?;
+
+    Ok((
+        effect_ids.into_iter().collect(),
+        attribute_ids.into_iter().collect(),
+    ))
+}
+
+fn language_list() -> String {
+    LANGUAGES
+        .iter()
+        .map(|language| format!("'{}'", language))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn import_sqlite(conn: &Connection, source: &Path) -> Result<(), Box<dyn Error>> {
+    conn.execute("ATTACH ? AS ext", [source.to_string_lossy().to_string()])?;
+
+    // The conversion keeps modifierInfo as the YAML it came from
+    let mut effects = BTreeMap::new();
+    {
+        let mut prepared = conn.prepare(
+            "SELECT effectID, modifierInfo FROM ext.dgmEffects WHERE modifierInfo IS NOT NULL",
+        )?;
+        let mut rows = prepared.query([])?;
+        while let Some(row) = rows.next()? {
+            let info: String = row.get(1)?;
+            if info.trim().is_empty() {
+                continue;
+            }
+            effects.insert(
+                row.get(0)?,
+                YamlEffect {
+                    modifiers: serde_yaml::from_str(&info)?,
+                },
+            );
+        }
+    }
+    let (effect_ids, attribute_ids) = import_modifiers(conn, &effects)?;
+
+    // Translation column 8 is invTypes.typeName. English comes from invTypes itself, since the
+    // translation table lists it as en-us.
+    conn.execute_batch(&format!(
+        "
+        INSERT INTO invTypes SELECT typeID, groupID, typeName, published FROM ext.invTypes;
+        INSERT INTO invGroups SELECT groupID, categoryID FROM ext.invGroups;
+        INSERT INTO invMetaTypes SELECT typeID, parentTypeID, metaGroupID FROM ext.invMetaTypes;
+        INSERT INTO dgmTypeAttributes SELECT typeID, attributeID, valueInt, valueFloat FROM ext.dgmTypeAttributes WHERE attributeID IN ({});
+        INSERT INTO dgmTypeEffects SELECT typeID, effectID FROM ext.dgmTypeEffects WHERE effectID IN ({});
+        INSERT INTO systems SELECT solarSystemID, solarSystemName FROM ext.mapSolarSystems;
+        INSERT INTO invTypeNames SELECT typeID, 'en', typeName FROM invTypes;
+        INSERT INTO invTypeNames SELECT keyID, LOWER(languageID), text FROM ext.trnTranslations
+            WHERE tcID = 8 AND LOWER(languageID) IN ({}) AND LOWER(languageID) != 'en';
+        ",
+        id_list(&attribute_ids),
+        id_list(&effect_ids),
+        language_list()
+    ))?;
+    conn.execute("DETACH ext", [])?;
+    Ok(())
+}
+
+fn import_yaml(conn: &Connection, dir: &Path) -> Result<(), Box<dyn Error>> {
+    let types: BTreeMap<i64, YamlType> = read_yaml(dir, &["fsd/types.yaml", "fsd/typeIDs.yaml"])?;
+    let groups: BTreeMap<i64, YamlGroup> =
+        read_yaml(dir, &["fsd/groups.yaml", "fsd/groupIDs.yaml"])?;
+    let dogma: BTreeMap<i64, YamlDogma> = read_yaml(dir, &["fsd/typeDogma.yaml"])?;
+    let names: Vec<YamlName> = read_yaml(dir, &["bsd/invNames.yaml"])?;
+    let effects: BTreeMap<i64, YamlEffect> =
+        read_yaml(dir, &["fsd/dogmaEffects.yaml", "fsd/dgmEffects.yaml"])?;
+    let (effect_ids, attribute_ids) = import_modifiers(conn, &effects)?;
+
+    let tx = conn.unchecked_transaction()?;
+    for (id, the_type) in &types {
+        let name = match the_type.name.get("en") {
+            Some(name) => name,
+            None => continue,
+        };
+        tx.execute(
+            "INSERT INTO invTypes VALUES (?, ?, ?, ?)",
+            params![id, the_type.group_id, name, the_type.published],
+        )?;
+        for (language, name) in &the_type.name {
+            if LANGUAGES.contains(&language.as_str()) {
+                tx.execute(
+                    "INSERT INTO invTypeNames VALUES (?, ?, ?)",
+                    params![id, language, name],
+                )?;
+            }
+        }
+        if let Some(meta_group_id) = the_type.meta_group_id {
+            tx.execute(
+                "INSERT INTO invMetaTypes VALUES (?, ?, ?)",
+                params![id, the_type.parent_type_id, meta_group_id],
+            )?;
+        }
+    }
+    for (id, group) in &groups {
+        tx.execute(
+            "INSERT INTO invGroups VALUES (?, ?)",
+            params![id, group.category_id],
+        )?;
+    }
+    for (id, type_dogma) in &dogma {
+        for attribute in &type_dogma.attributes {
+            if attribute_ids.contains(&attribute.attribute_id) {
+                tx.execute(
+                    "INSERT INTO dgmTypeAttributes VALUES (?, ?, NULL, ?)",
+                    params![id, attribute.attribute_id, attribute.value],
+                )?;
+            }
+        }
+        for effect in &type_dogma.effects {
+            if effect_ids.contains(&effect.effect_id) {
+                tx.execute(
+                    "INSERT INTO dgmTypeEffects VALUES (?, ?)",
+                    params![id, effect.effect_id],
+                )?;
+            }
+        }
+    }
+    for name in &names {
+        // Solar systems live in their own ID range
+        if (30000000..33000000).contains(&name.item_id) {
+            tx.execute(
+                "INSERT INTO systems VALUES (?, ?)",
+                params![name.item_id, name.item_name],
+            )?;
+        }
+    }
+    tx.commit()?;
+
+    Ok(())
+}
+
+fn compare(conn: &Connection, previous: &Path) -> Result<Vec<Change>, Box<dyn Error>> {
+    conn.execute("ATTACH ? AS prev", [previous.to_string_lossy().to_string()])?;
+
+    let mut changes = Vec::new();
+    {
+        let mut removed = conn.prepare(
+            "SELECT p.typeID, p.typeName FROM prev.invTypes p
+            LEFT JOIN invTypes t ON t.typeID = p.typeID WHERE t.typeID IS NULL",
+        )?;
+        for row in removed.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))? {
+            let (id, name) = row?;
+            changes.push(Change::Removed { id, name });
+        }
+
+        let mut renamed = conn.prepare(
+            "SELECT p.typeID, p.typeName, t.typeName FROM prev.invTypes p
+            JOIN invTypes t ON t.typeID = p.typeID WHERE t.typeName != p.typeName",
+        )?;
+        for row in renamed.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))? {
+            let (id, from, to) = row?;
+            changes.push(Change::Renamed { id, from, to });
+        }
+
+        let mut unpublished = conn.prepare(
+            "SELECT t.typeID, t.typeName FROM prev.invTypes p
+            JOIN invTypes t ON t.typeID = p.typeID WHERE p.published = 1 AND t.published = 0",
+        )?;
+        for row in unpublished.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))? {
+            let (id, name) = row?;
+            changes.push(Change::Unpublished { id, name });
+        }
+    }
+
+    conn.execute("DETACH prev", [])?;
+    Ok(changes)
+}
+
+fn run(source: &Path, output: &Path, report: Option<&Path>) -> Result<(), Box<dyn Error>> {
+    let building = output.with_extension("new");
+    if building.exists() {
+        fs::remove_file(&building)?;
+    }
+
+    // The SQLite conversion is usually distributed compressed
+    let decompressed = output.with_extension("sde");
+    let source = if source.extension().and_then(|e| e.to_str()) == Some("bz2") {
+        println!("Decompressing {}", source.display());
+        let mut reader = bzip2::read::BzDecoder::new(File::open(source)?);
+        io::copy(&mut reader, &mut File::create(&decompressed)?)?;
+        decompressed.as_path()
+    } else {
+        source
+    };
+
+    let conn = Connection::open(&building)?;
+    conn.execute_batch(SCHEMA)?;
+    if source.is_dir() {
+        import_yaml(&conn, source)?;
+    } else {
+        import_sqlite(&conn, source)?;
+    }
+    conn.execute_batch(INDEXES)?;
+    conn.execute_batch(FAMILIES)?;
+
+    let changes = match output.exists() {
+        true => compare(&conn, output)?,
+        false => Vec::new(),
+    };
+    drop(conn);
+
+    if decompressed.exists() {
+        fs::remove_file(&decompressed)?;
+    }
+    fs::rename(&building, output)?;
+
+    let mut lines: Vec<String> = changes.iter().map(|change| change.to_string()).collect();
+    lines.sort();
+    for line in &lines {
+        println!("{}", line);
+    }
+    println!(
+        "Wrote {}, {} type changes since the previous database",
+        output.display(),
+        lines.len()
+    );
+    if let Some(report) = report {
+        fs::write(report, lines.join("\n") + "\n")?;
+    }
+
+    Ok(())
+}
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let mut source = None;
+    let mut output = String::from("sqlite-shrunk.sqlite");
+    let mut report = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--output" => output = args.next().expect("--output needs a path"),
+            "--report" => report = Some(args.next().expect("--report needs a path")),
+            _ => source = Some(arg),
+        }
+    }
+
+    let source = match source {
+        Some(source) => source,
+        None => {
+            eprintln!(
+                "Usage: sde_import <source> [--output sqlite-shrunk.sqlite] [--report report.txt]"
+            );
+            std::process::exit(2);
+        }
+    };
+
+    if let Err(e) = run(
+        Path::new(&source),
+        Path::new(&output),
+        report.as_deref().map(Path::new),
+    ) {
+        eprintln!("SDE import failed: {}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,13 @@
+-- Cached time in fleet per character and hull, kept up to date by the fleet updater
+CREATE TABLE fleet_time (
+  character_id BIGINT NOT NULL,
+  hull INT NOT NULL,
+  seconds BIGINT NOT NULL,
+  PRIMARY KEY (character_id, hull),
+  CONSTRAINT fleet_time_character_id FOREIGN KEY (character_id) REFERENCES character (id)
+);
+
+-- Backfill from the existing fleet activity
+INSERT INTO fleet_time (character_id, hull, seconds)
+SELECT character_id, hull, CAST(SUM(last_seen - first_seen) AS BIGINT) FROM fleet_activity
+GROUP BY character_id, hull;
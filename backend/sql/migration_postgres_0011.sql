@@ -0,0 +1,12 @@
+-- Hour milestones the pilot has been warned about, shown to FCs alongside the notes
+CREATE TABLE pending_upgrade (
+  id BIGINT NOT NULL PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+  character_id BIGINT NOT NULL,
+  hull INT NOT NULL,
+  hours BIGINT NOT NULL,
+  tag VARCHAR(32) NOT NULL,
+  state VARCHAR(16) NOT NULL CHECK (state IN ('approaching', 'reached')),
+  logged_at BIGINT NOT NULL,
+  UNIQUE (character_id, hull, hours, state),
+  CONSTRAINT pending_upgrade_character_id FOREIGN KEY (character_id) REFERENCES character (id)
+);
@@ -0,0 +1,7 @@
+-- Characters whose refresh token was revoked, cleared on the next login
+CREATE TABLE revoked_token (
+  character_id BIGINT NOT NULL,
+  revoked_at BIGINT NOT NULL,
+  PRIMARY KEY (character_id),
+  CONSTRAINT revoked_token_character_id FOREIGN KEY (character_id) REFERENCES character (id)
+);
@@ -0,0 +1,19 @@
+-- Track which expired bans FCs have been told about, bans that already expired don't need a notice
+ALTER TABLE ban ADD COLUMN expiry_notified BOOLEAN NOT NULL DEFAULT FALSE;
+UPDATE ban SET expiry_notified = TRUE WHERE revoked_at IS NOT NULL AND revoked_at <= EXTRACT(EPOCH FROM NOW());
+
+-- Appeals submitted by banned pilots
+CREATE TABLE ban_appeal (
+  id BIGINT NOT NULL PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+  ban_id BIGINT NOT NULL,
+  character_id BIGINT NOT NULL,
+  appeal TEXT NOT NULL,
+  submitted_at BIGINT NOT NULL,
+  state VARCHAR(10) NOT NULL DEFAULT 'pending' CHECK (state IN ('pending', 'accepted', 'rejected')),
+  reviewed_by BIGINT,
+  reviewed_at BIGINT,
+  review_comment VARCHAR(512),
+  CONSTRAINT ban_appeal_ban_id FOREIGN KEY (ban_id) REFERENCES ban (id) ON DELETE CASCADE,
+  CONSTRAINT ban_appeal_character_id FOREIGN KEY (character_id) REFERENCES character (id),
+  CONSTRAINT ban_appeal_reviewed_by FOREIGN KEY (reviewed_by) REFERENCES character (id)
+);
@@ -0,0 +1,9 @@
+-- Signals of possible ban evasion found by the evasion detector
+CREATE TABLE evasion_flag (
+  character_id BIGINT NOT NULL,
+  reason VARCHAR(32) NOT NULL,
+  details VARCHAR(512) NOT NULL,
+  flagged_at BIGINT NOT NULL,
+  PRIMARY KEY (character_id, reason),
+  CONSTRAINT evasion_flag_character_id FOREIGN KEY (character_id) REFERENCES character (id)
+);
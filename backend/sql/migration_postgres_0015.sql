@@ -0,0 +1,11 @@
+-- Corporation and alliance changes picked up by the affiliation updater
+CREATE TABLE affiliation_history (
+  id BIGINT NOT NULL PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+  character_id BIGINT NOT NULL,
+  old_corporation_id BIGINT,
+  old_alliance_id BIGINT,
+  corporation_id BIGINT NOT NULL,
+  alliance_id BIGINT,
+  changed_at BIGINT NOT NULL,
+  CONSTRAINT affiliation_history_character_id FOREIGN KEY (character_id) REFERENCES character (id)
+);
@@ -0,0 +1,119 @@
+-- Roles and their access keys are stored in the database, seeded with the existing ladder.
+-- Characters can hold more than one role.
+CREATE TABLE role (
+  name VARCHAR(64) NOT NULL PRIMARY KEY,
+  sort_order INT NOT NULL
+);
+
+CREATE TABLE role_permission (
+  role VARCHAR(64) NOT NULL,
+  permission VARCHAR(64) NOT NULL,
+  PRIMARY KEY (role, permission),
+  CONSTRAINT role_permission_role FOREIGN KEY (role) REFERENCES role (name) ON DELETE CASCADE
+);
+
+INSERT INTO role (name, sort_order) VALUES
+  ('Wiki Team', 10),
+  ('Trainee', 20),
+  ('FC', 30),
+  ('Instructor', 40),
+  ('Leadership', 50);
+
+INSERT INTO role_permission (role, permission) VALUES
+  ('Wiki Team', 'wiki-editor'),
+  ('Trainee', 'fleet-configure'),
+  ('Trainee', 'fleet-invite'),
+  ('Trainee', 'fleet-view'),
+  ('Trainee', 'pilot-view'),
+  ('Trainee', 'waitlist-view'),
+  ('Trainee', 'waitlist-tag:TRAINEE'),
+  ('Trainee', 'fit-view'),
+  ('Trainee', 'skill-view'),
+  ('Trainee', 'waitlist-manage'),
+  ('FC', 'fleet-configure'),
+  ('FC', 'fleet-invite'),
+  ('FC', 'fleet-view'),
+  ('FC', 'pilot-view'),
+  ('FC', 'waitlist-view'),
+  ('FC', 'waitlist-tag:TRAINEE'),
+  ('FC', 'fit-view'),
+  ('FC', 'skill-view'),
+  ('FC', 'waitlist-manage'),
+  ('FC', 'bans-manage'),
+  ('FC', 'badges-manage'),
+  ('FC', 'commanders-view'),
+  ('FC', 'fleet-activity-view'),
+  ('FC', 'fleet-history-view'),
+  ('FC', 'fit-history-view'),
+  ('FC', 'search'),
+  ('FC', 'skill-history-view'),
+  ('FC', 'waitlist-edit'),
+  ('FC', 'stats-view'),
+  ('FC', 'waitlist-tag:HQ-FC'),
+  ('FC', 'notes-view'),
+  ('FC', 'notes-add'),
+  ('Instructor', 'fleet-configure'),
+  ('Instructor', 'fleet-invite'),
+  ('Instructor', 'fleet-view'),
+  ('Instructor', 'pilot-view'),
+  ('Instructor', 'waitlist-view'),
+  ('Instructor', 'waitlist-tag:TRAINEE'),
+  ('Instructor', 'fit-view'),
+  ('Instructor', 'skill-view'),
+  ('Instructor', 'waitlist-manage'),
+  ('Instructor', 'bans-manage'),
+  ('Instructor', 'badges-manage'),
+  ('Instructor', 'commanders-view'),
+  ('Instructor', 'fleet-activity-view'),
+  ('Instructor', 'fleet-history-view'),
+  ('Instructor', 'fit-history-view'),
+  ('Instructor', 'search'),
+  ('Instructor', 'skill-history-view'),
+  ('Instructor', 'waitlist-edit'),
+  ('Instructor', 'stats-view'),
+  ('Instructor', 'waitlist-tag:HQ-FC'),
+  ('Instructor', 'notes-view'),
+  ('Instructor', 'notes-add'),
+  ('Instructor', 'commanders-manage'),
+  ('Instructor', 'commanders-manage:Trainee'),
+  ('Instructor', 'commanders-manage:FC'),
+  ('Instructor', 'fleet-admin'),
+  ('Instructor', 'reports-view'),
+  ('Leadership', 'fleet-configure'),
+  ('Leadership', 'fleet-invite'),
+  ('Leadership', 'fleet-view'),
+  ('Leadership', 'pilot-view'),
+  ('Leadership', 'waitlist-view'),
+  ('Leadership', 'waitlist-tag:TRAINEE'),
+  ('Leadership', 'fit-view'),
+  ('Leadership', 'skill-view'),
+  ('Leadership', 'waitlist-manage'),
+  ('Leadership', 'bans-manage'),
+  ('Leadership', 'badges-manage'),
+  ('Leadership', 'commanders-view'),
+  ('Leadership', 'fleet-activity-view'),
+  ('Leadership', 'fleet-history-view'),
+  ('Leadership', 'fit-history-view'),
+  ('Leadership', 'search'),
+  ('Leadership', 'skill-history-view'),
+  ('Leadership', 'waitlist-edit'),
+  ('Leadership', 'stats-view'),
+  ('Leadership', 'waitlist-tag:HQ-FC'),
+  ('Leadership', 'notes-view'),
+  ('Leadership', 'notes-add'),
+  ('Leadership', 'commanders-manage'),
+  ('Leadership', 'commanders-manage:Trainee'),
+  ('Leadership', 'commanders-manage:FC'),
+  ('Leadership', 'fleet-admin'),
+  ('Leadership', 'reports-view'),
+  ('Leadership', 'commanders-manage:Wiki Team'),
+  ('Leadership', 'commanders-manage:Instructor'),
+  ('Leadership', 'commanders-manage:Leadership'),
+  ('Leadership', 'roles-manage');
+
+-- Keep any role that was granted but isn't part of the ladder, it simply has no access keys
+INSERT INTO role (name, sort_order) SELECT DISTINCT role, 0 FROM admin ON CONFLICT DO NOTHING;
+
+ALTER TABLE admin DROP CONSTRAINT admin_pkey;
+ALTER TABLE admin ADD PRIMARY KEY (character_id, role);
+ALTER TABLE admin ADD CONSTRAINT admin_role FOREIGN KEY (role) REFERENCES role (name);
@@ -0,0 +1,16 @@
+-- Role grants can expire, expired grants are revoked by the role expiry job and logged
+ALTER TABLE admin ADD COLUMN expires_at BIGINT;
+ALTER TABLE admin ADD COLUMN reason VARCHAR(512);
+ALTER TABLE admin ADD COLUMN expiry_warned BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE TABLE role_expiry_log (
+  id BIGINT NOT NULL PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+  character_id BIGINT NOT NULL,
+  role VARCHAR(64) NOT NULL,
+  granted_by_id BIGINT NOT NULL,
+  granted_at BIGINT NOT NULL,
+  reason VARCHAR(512),
+  expired_at BIGINT NOT NULL,
+  CONSTRAINT role_expiry_log_character_id FOREIGN KEY (character_id) REFERENCES character (id),
+  CONSTRAINT role_expiry_log_granted_by_id FOREIGN KEY (granted_by_id) REFERENCES character (id)
+);
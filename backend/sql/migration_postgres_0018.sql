@@ -0,0 +1,14 @@
+-- Every privileged action is written to the audit log, only Leadership can read it
+CREATE TABLE audit_log (
+  id BIGINT NOT NULL PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+  actor_id BIGINT NOT NULL,
+  action VARCHAR(64) NOT NULL,
+  target_type VARCHAR(64) NOT NULL,
+  target_id BIGINT,
+  before TEXT,
+  after TEXT,
+  logged_at BIGINT NOT NULL,
+  CONSTRAINT audit_log_actor_id FOREIGN KEY (actor_id) REFERENCES character (id)
+);
+
+INSERT INTO role_permission (role, permission) VALUES ('Leadership', 'audit-view') ON CONFLICT DO NOTHING;
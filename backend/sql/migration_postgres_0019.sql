@@ -0,0 +1,11 @@
+-- Personal API tokens, sent as a Bearer token by bots and external tools
+CREATE TABLE api_token (
+  id BIGINT NOT NULL PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+  character_id BIGINT NOT NULL,
+  name VARCHAR(64) NOT NULL,
+  scopes TEXT NOT NULL,
+  created_at BIGINT NOT NULL,
+  expires_at BIGINT,
+  last_used_at BIGINT,
+  CONSTRAINT api_token_character_id FOREIGN KEY (character_id) REFERENCES character (id)
+);
@@ -0,0 +1,9 @@
+-- Login sessions referenced from the auth cookie, existing cookies are given one on their next request
+CREATE TABLE session (
+  id BIGINT NOT NULL PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+  character_id BIGINT NOT NULL,
+  created_at BIGINT NOT NULL,
+  last_seen BIGINT NOT NULL,
+  user_agent TEXT,
+  CONSTRAINT session_character_id FOREIGN KEY (character_id) REFERENCES character (id)
+);
@@ -0,0 +1,2 @@
+-- Module alternatives are generated from the SDE, Leadership can compare them against modules.yaml
+INSERT INTO role_permission (role, permission) VALUES ('Leadership', 'modules-manage') ON CONFLICT DO NOTHING;
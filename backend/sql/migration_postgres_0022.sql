@@ -0,0 +1,10 @@
+-- Time spent as fleet boss, so reports can use the cached time in fleet
+ALTER TABLE fleet_time ADD COLUMN boss_seconds BIGINT NOT NULL DEFAULT 0;
+
+UPDATE fleet_time SET boss_seconds = boss.seconds
+FROM (
+  SELECT character_id, hull, CAST(SUM(last_seen - first_seen) AS BIGINT) AS seconds FROM fleet_activity
+  WHERE is_boss = true
+  GROUP BY character_id, hull
+) AS boss
+WHERE fleet_time.character_id = boss.character_id AND fleet_time.hull = boss.hull;
@@ -0,0 +1,16 @@
+-- The characters and scopes an account was last asked to re-auth for, so the prompt is only sent again when they change
+CREATE TABLE reauth_prompt (
+  account_id BIGINT NOT NULL,
+  missing TEXT NOT NULL,
+  PRIMARY KEY (account_id),
+  CONSTRAINT reauth_prompt_account_id FOREIGN KEY (account_id) REFERENCES character (id)
+);
+
+-- Result of the last refresh token check, set when ESI could not be reached for the character
+CREATE TABLE token_check (
+  character_id BIGINT NOT NULL,
+  checked_at BIGINT NOT NULL,
+  error TEXT NULL,
+  PRIMARY KEY (character_id),
+  CONSTRAINT token_check_character_id FOREIGN KEY (character_id) REFERENCES character (id)
+);
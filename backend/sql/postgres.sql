@@ -37,13 +37,165 @@ CREATE TABLE refresh_token (
   CONSTRAINT refresh_token_character_id FOREIGN KEY (character_id) REFERENCES character (id)
 );
 
+CREATE TABLE revoked_token (
+  character_id BIGINT NOT NULL,
+  revoked_at BIGINT NOT NULL,
+  PRIMARY KEY (character_id),
+  CONSTRAINT revoked_token_character_id FOREIGN KEY (character_id) REFERENCES character (id)
+);
+
+CREATE TABLE reauth_prompt (
+  account_id BIGINT NOT NULL,
+  missing TEXT NOT NULL,
+  PRIMARY KEY (account_id),
+  CONSTRAINT reauth_prompt_account_id FOREIGN KEY (account_id) REFERENCES character (id)
+);
+
+CREATE TABLE token_check (
+  character_id BIGINT NOT NULL,
+  checked_at BIGINT NOT NULL,
+  error TEXT NULL,
+  PRIMARY KEY (character_id),
+  CONSTRAINT token_check_character_id FOREIGN KEY (character_id) REFERENCES character (id)
+);
+
+CREATE TABLE role (
+  name VARCHAR(64) NOT NULL PRIMARY KEY,
+  sort_order INT NOT NULL
+);
+
+CREATE TABLE role_permission (
+  role VARCHAR(64) NOT NULL,
+  permission VARCHAR(64) NOT NULL,
+  PRIMARY KEY (role, permission),
+  CONSTRAINT role_permission_role FOREIGN KEY (role) REFERENCES role (name) ON DELETE CASCADE
+);
+
+INSERT INTO role (name, sort_order) VALUES
+  ('Wiki Team', 10),
+  ('Trainee', 20),
+  ('FC', 30),
+  ('Instructor', 40),
+  ('Leadership', 50);
+
+INSERT INTO role_permission (role, permission) VALUES
+  ('Wiki Team', 'wiki-editor'),
+  ('Trainee', 'fleet-configure'),
+  ('Trainee', 'fleet-invite'),
+  ('Trainee', 'fleet-view'),
+  ('Trainee', 'pilot-view'),
+  ('Trainee', 'waitlist-view'),
+  ('Trainee', 'waitlist-tag:TRAINEE'),
+  ('Trainee', 'fit-view'),
+  ('Trainee', 'skill-view'),
+  ('Trainee', 'waitlist-manage'),
+  ('FC', 'fleet-configure'),
+  ('FC', 'fleet-invite'),
+  ('FC', 'fleet-view'),
+  ('FC', 'pilot-view'),
+  ('FC', 'waitlist-view'),
+  ('FC', 'waitlist-tag:TRAINEE'),
+  ('FC', 'fit-view'),
+  ('FC', 'skill-view'),
+  ('FC', 'waitlist-manage'),
+  ('FC', 'bans-manage'),
+  ('FC', 'badges-manage'),
+  ('FC', 'commanders-view'),
+  ('FC', 'fleet-activity-view'),
+  ('FC', 'fleet-history-view'),
+  ('FC', 'fit-history-view'),
+  ('FC', 'search'),
+  ('FC', 'skill-history-view'),
+  ('FC', 'waitlist-edit'),
+  ('FC', 'stats-view'),
+  ('FC', 'waitlist-tag:HQ-FC'),
+  ('FC', 'notes-view'),
+  ('FC', 'notes-add'),
+  ('Instructor', 'fleet-configure'),
+  ('Instructor', 'fleet-invite'),
+  ('Instructor', 'fleet-view'),
+  ('Instructor', 'pilot-view'),
+  ('Instructor', 'waitlist-view'),
+  ('Instructor', 'waitlist-tag:TRAINEE'),
+  ('Instructor', 'fit-view'),
+  ('Instructor', 'skill-view'),
+  ('Instructor', 'waitlist-manage'),
+  ('Instructor', 'bans-manage'),
+  ('Instructor', 'badges-manage'),
+  ('Instructor', 'commanders-view'),
+  ('Instructor', 'fleet-activity-view'),
+  ('Instructor', 'fleet-history-view'),
+  ('Instructor', 'fit-history-view'),
+  ('Instructor', 'search'),
+  ('Instructor', 'skill-history-view'),
+  ('Instructor', 'waitlist-edit'),
+  ('Instructor', 'stats-view'),
+  ('Instructor', 'waitlist-tag:HQ-FC'),
+  ('Instructor', 'notes-view'),
+  ('Instructor', 'notes-add'),
+  ('Instructor', 'commanders-manage'),
+  ('Instructor', 'commanders-manage:Trainee'),
+  ('Instructor', 'commanders-manage:FC'),
+  ('Instructor', 'fleet-admin'),
+  ('Instructor', 'reports-view'),
+  ('Leadership', 'fleet-configure'),
+  ('Leadership', 'fleet-invite'),
+  ('Leadership', 'fleet-view'),
+  ('Leadership', 'pilot-view'),
+  ('Leadership', 'waitlist-view'),
+  ('Leadership', 'waitlist-tag:TRAINEE'),
+  ('Leadership', 'fit-view'),
+  ('Leadership', 'skill-view'),
+  ('Leadership', 'waitlist-manage'),
+  ('Leadership', 'bans-manage'),
+  ('Leadership', 'badges-manage'),
+  ('Leadership', 'commanders-view'),
+  ('Leadership', 'fleet-activity-view'),
+  ('Leadership', 'fleet-history-view'),
+  ('Leadership', 'fit-history-view'),
+  ('Leadership', 'search'),
+  ('Leadership', 'skill-history-view'),
+  ('Leadership', 'waitlist-edit'),
+  ('Leadership', 'stats-view'),
+  ('Leadership', 'waitlist-tag:HQ-FC'),
+  ('Leadership', 'notes-view'),
+  ('Leadership', 'notes-add'),
+  ('Leadership', 'commanders-manage'),
+  ('Leadership', 'commanders-manage:Trainee'),
+  ('Leadership', 'commanders-manage:FC'),
+  ('Leadership', 'fleet-admin'),
+  ('Leadership', 'reports-view'),
+  ('Leadership', 'commanders-manage:Wiki Team'),
+  ('Leadership', 'commanders-manage:Instructor'),
+  ('Leadership', 'commanders-manage:Leadership'),
+  ('Leadership', 'roles-manage'),
+  ('Leadership', 'audit-view'),
+  ('Leadership', 'modules-manage');
+
 CREATE TABLE admin (
-  character_id BIGINT PRIMARY KEY NOT NULL,
+  character_id BIGINT NOT NULL,
   role VARCHAR(64) NOT NULL,
   granted_at BIGINT NOT NULL,
   granted_by_id BIGINT NOT NULL,
+  expires_at BIGINT,
+  reason VARCHAR(512),
+  expiry_warned BOOLEAN NOT NULL DEFAULT FALSE,
+  PRIMARY KEY (character_id, role),
   CONSTRAINT character_role FOREIGN KEY (character_id) REFERENCES character (id),
-  CONSTRAINT admin_character FOREIGN KEY (granted_by_id) REFERENCES character (id)
+  CONSTRAINT admin_character FOREIGN KEY (granted_by_id) REFERENCES character (id),
+  CONSTRAINT admin_role FOREIGN KEY (role) REFERENCES role (name)
+);
+
+CREATE TABLE role_expiry_log (
+  id BIGINT NOT NULL PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+  character_id BIGINT NOT NULL,
+  role VARCHAR(64) NOT NULL,
+  granted_by_id BIGINT NOT NULL,
+  granted_at BIGINT NOT NULL,
+  reason VARCHAR(512),
+  expired_at BIGINT NOT NULL,
+  CONSTRAINT role_expiry_log_character_id FOREIGN KEY (character_id) REFERENCES character (id),
+  CONSTRAINT role_expiry_log_granted_by_id FOREIGN KEY (granted_by_id) REFERENCES character (id)
 );
 
 CREATE TABLE alt_character (
@@ -79,10 +231,78 @@ CREATE TABLE ban (
   reason VARCHAR(512) NOT NULL,
   revoked_at BIGINT,
   revoked_by BIGINT,
+  expiry_notified BOOLEAN NOT NULL DEFAULT FALSE,
   CONSTRAINT issued_by FOREIGN KEY (issued_by) REFERENCES character (id),
   CONSTRAINT revoked_by FOREIGN KEY (revoked_by) REFERENCES character (id)
 );
 
+CREATE TABLE ban_appeal (
+  id BIGINT NOT NULL PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+  ban_id BIGINT NOT NULL,
+  character_id BIGINT NOT NULL,
+  appeal TEXT NOT NULL,
+  submitted_at BIGINT NOT NULL,
+  state VARCHAR(10) NOT NULL DEFAULT 'pending' CHECK (state IN ('pending', 'accepted', 'rejected')),
+  reviewed_by BIGINT,
+  reviewed_at BIGINT,
+  review_comment VARCHAR(512),
+  CONSTRAINT ban_appeal_ban_id FOREIGN KEY (ban_id) REFERENCES ban (id) ON DELETE CASCADE,
+  CONSTRAINT ban_appeal_character_id FOREIGN KEY (character_id) REFERENCES character (id),
+  CONSTRAINT ban_appeal_reviewed_by FOREIGN KEY (reviewed_by) REFERENCES character (id)
+);
+
+CREATE TABLE evasion_flag (
+  character_id BIGINT NOT NULL,
+  reason VARCHAR(32) NOT NULL,
+  details VARCHAR(512) NOT NULL,
+  flagged_at BIGINT NOT NULL,
+  PRIMARY KEY (character_id, reason),
+  CONSTRAINT evasion_flag_character_id FOREIGN KEY (character_id) REFERENCES character (id)
+);
+
+CREATE TABLE affiliation_history (
+  id BIGINT NOT NULL PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+  character_id BIGINT NOT NULL,
+  old_corporation_id BIGINT,
+  old_alliance_id BIGINT,
+  corporation_id BIGINT NOT NULL,
+  alliance_id BIGINT,
+  changed_at BIGINT NOT NULL,
+  CONSTRAINT affiliation_history_character_id FOREIGN KEY (character_id) REFERENCES character (id)
+);
+
+CREATE TABLE audit_log (
+  id BIGINT NOT NULL PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+  actor_id BIGINT NOT NULL,
+  action VARCHAR(64) NOT NULL,
+  target_type VARCHAR(64) NOT NULL,
+  target_id BIGINT,
+  before TEXT,
+  after TEXT,
+  logged_at BIGINT NOT NULL,
+  CONSTRAINT audit_log_actor_id FOREIGN KEY (actor_id) REFERENCES character (id)
+);
+
+CREATE TABLE api_token (
+  id BIGINT NOT NULL PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+  character_id BIGINT NOT NULL,
+  name VARCHAR(64) NOT NULL,
+  scopes TEXT NOT NULL,
+  created_at BIGINT NOT NULL,
+  expires_at BIGINT,
+  last_used_at BIGINT,
+  CONSTRAINT api_token_character_id FOREIGN KEY (character_id) REFERENCES character (id)
+);
+
+CREATE TABLE session (
+  id BIGINT NOT NULL PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+  character_id BIGINT NOT NULL,
+  created_at BIGINT NOT NULL,
+  last_seen BIGINT NOT NULL,
+  user_agent TEXT,
+  CONSTRAINT session_character_id FOREIGN KEY (character_id) REFERENCES character (id)
+);
+
 CREATE TABLE badge (
   id BIGINT NOT NULL PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
   name VARCHAR(64) NOT NULL UNIQUE,
@@ -143,6 +363,15 @@ CREATE TABLE fleet_activity (
   CONSTRAINT fleet_activity_character_id FOREIGN KEY (character_id) REFERENCES character (id)
 );
 
+CREATE TABLE fleet_time (
+  character_id BIGINT NOT NULL,
+  hull INT NOT NULL,
+  seconds BIGINT NOT NULL,
+  boss_seconds BIGINT NOT NULL DEFAULT 0,
+  PRIMARY KEY (character_id, hull),
+  CONSTRAINT fleet_time_character_id FOREIGN KEY (character_id) REFERENCES character (id)
+);
+
 CREATE TABLE skill_current (
   character_id BIGINT NOT NULL,
   skill_id INT NOT NULL,
@@ -171,6 +400,18 @@ CREATE TABLE character_note (
   CONSTRAINT character_note_author_id FOREIGN KEY (author_id) REFERENCES character (id)
 );
 
+CREATE TABLE pending_upgrade (
+  id BIGINT NOT NULL PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+  character_id BIGINT NOT NULL,
+  hull INT NOT NULL,
+  hours BIGINT NOT NULL,
+  tag VARCHAR(32) NOT NULL,
+  state VARCHAR(16) NOT NULL CHECK (state IN ('approaching', 'reached')),
+  logged_at BIGINT NOT NULL,
+  UNIQUE (character_id, hull, hours, state),
+  CONSTRAINT pending_upgrade_character_id FOREIGN KEY (character_id) REFERENCES character (id)
+);
+
 -- Temporary things
 
 CREATE TABLE fleet (
@@ -8,7 +8,9 @@ pub struct Application {
     pub ban_service: crate::core::ban::BanService,
     pub esi_client: crate::core::esi::ESIClient,
     pub sse_client: crate::core::sse::SSEClient,
+    pub time_in_fleet: crate::core::time_in_fleet::TimeInFleetService,
     pub token_secret: Vec<u8>,
+    pub webhook_client: crate::core::webhook::WebhookClient,
 }
 
 pub fn new(db: Arc<crate::DB>, config: Config) -> Application {
@@ -31,7 +33,9 @@ pub fn new(db: Arc<crate::DB>, config: Config) -> Application {
             config.sse.url.clone(),
             &hex::decode(&config.sse.secret).unwrap(),
         ),
+        time_in_fleet: crate::core::time_in_fleet::TimeInFleetService::new(db.clone()),
         token_secret: hex::decode(&config.app.token_secret).unwrap(),
+        webhook_client: crate::core::webhook::WebhookClient::new(config.webhook.clone()),
         db,
         config,
     }
@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use serde::Deserialize;
 
 #[derive(Deserialize, Clone)]
@@ -39,6 +41,115 @@ pub struct SkillUpdaterConfig {
     pub runtime: f64,
 }
 
+#[derive(Deserialize, Clone)]
+pub struct AffiliationUpdaterConfig {
+    pub enable: bool,
+    pub interval: u64,
+    pub seen_days: i64,
+}
+
+impl Default for AffiliationUpdaterConfig {
+    fn default() -> Self {
+        AffiliationUpdaterConfig {
+            enable: false,
+            interval: 3600,
+            seen_days: 28,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone)]
+pub struct ScopeCheckerConfig {
+    pub enable: bool,
+    pub interval: u64,
+}
+
+impl Default for ScopeCheckerConfig {
+    fn default() -> Self {
+        ScopeCheckerConfig {
+            enable: false,
+            interval: 3600,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone)]
+pub struct MilestoneNotifierConfig {
+    pub enable: bool,
+    pub warn_hours: i64,
+}
+
+impl Default for MilestoneNotifierConfig {
+    fn default() -> Self {
+        MilestoneNotifierConfig {
+            enable: false,
+            warn_hours: 5,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone)]
+pub struct BanNotifierConfig {
+    pub enable: bool,
+}
+
+impl Default for BanNotifierConfig {
+    fn default() -> Self {
+        BanNotifierConfig { enable: false }
+    }
+}
+
+#[derive(Deserialize, Clone)]
+pub struct EvasionDetectorConfig {
+    pub enable: bool,
+    pub corp_hop_days: i64,
+}
+
+impl Default for EvasionDetectorConfig {
+    fn default() -> Self {
+        EvasionDetectorConfig {
+            enable: false,
+            corp_hop_days: 14,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone)]
+pub struct RoleExpiryConfig {
+    pub enable: bool,
+    pub warn_hours: i64,
+}
+
+impl Default for RoleExpiryConfig {
+    fn default() -> Self {
+        RoleExpiryConfig {
+            enable: false,
+            warn_hours: 48,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone)]
+pub struct WebhookConfig {
+    pub enable: bool,
+    pub url: String,
+    pub retries: u32,
+    pub retry_delay_ms: u64,
+    pub templates: BTreeMap<String, String>,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        WebhookConfig {
+            enable: false,
+            url: String::new(),
+            retries: 3,
+            retry_delay_ms: 2000,
+            templates: BTreeMap::new(),
+        }
+    }
+}
+
 #[derive(Deserialize, Clone)]
 pub struct DokuWikiConfig {
     pub mail_domain: String,
@@ -52,5 +163,19 @@ pub struct Config {
     pub sse: SSEConfig,
     pub fleet_updater: FleetUpdaterConfig,
     pub skill_updater: SkillUpdaterConfig,
+    #[serde(default)]
+    pub affiliation_updater: AffiliationUpdaterConfig,
+    #[serde(default)]
+    pub scope_checker: ScopeCheckerConfig,
+    #[serde(default)]
+    pub milestone_notifier: MilestoneNotifierConfig,
+    #[serde(default)]
+    pub ban_notifier: BanNotifierConfig,
+    #[serde(default)]
+    pub evasion_detector: EvasionDetectorConfig,
+    #[serde(default)]
+    pub role_expiry: RoleExpiryConfig,
+    #[serde(default)]
+    pub webhook: WebhookConfig,
     pub dokuwiki: DokuWikiConfig,
 }
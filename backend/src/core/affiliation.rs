@@ -1,5 +1,8 @@
 use serde::Deserialize;
-use std::sync::Arc;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Arc,
+};
 
 use crate::util::madness::Madness;
 
@@ -20,6 +23,22 @@ struct AllianceResponse {
     name: String,
 }
 
+#[derive(Debug, Deserialize)]
+struct AffiliationResponse {
+    character_id: i64,
+    corporation_id: i64,
+    alliance_id: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NameResponse {
+    id: i64,
+    name: String,
+}
+
+// ESI accepts at most 1000 ids per bulk request
+const BULK_LIMIT: usize = 1000;
+
 pub struct AffiliationService {
     db: Arc<crate::DB>,
     esi_client: crate::core::esi::ESIClient,
@@ -41,6 +60,7 @@ impl AffiliationService {
             .esi_client
             .get_unauthenticated(&format!("/latest/characters/{}", id))
             .await?;
+        let previous = self.current_affiliation(id).await?;
         self.update_corp_affiliation(character.corporation_id)
             .await?;
 
@@ -67,9 +87,209 @@ impl AffiliationService {
             .await?;
         }
 
+        if let Some((corporation_id, alliance_id)) = previous {
+            let alliance = sqlx::query!(
+                "SELECT alliance_id FROM corporation WHERE id=$1",
+                character.corporation_id
+            )
+            .fetch_one(self.db.as_ref())
+            .await?;
+            self.record_change(
+                id,
+                (corporation_id, alliance_id),
+                (character.corporation_id, alliance.alliance_id),
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Refreshes the affiliations of many characters at once using ESI's bulk endpoints,
+    /// returns the number of characters whose corporation or alliance changed
+    pub async fn update_affiliations(&self, ids: &[i64]) -> Result<usize, Madness> {
+        let mut changed = 0;
+        for chunk in ids.chunks(BULK_LIMIT) {
+            let affiliations: Vec<AffiliationResponse> = self
+                .esi_client
+                .post_unauthenticated("/latest/characters/affiliation/", chunk)
+                .await?;
+
+            // Read the previous affiliations first, updating the corporations overwrites their alliance
+            let mut previous = BTreeMap::new();
+            for affiliation in &affiliations {
+                previous.insert(
+                    affiliation.character_id,
+                    self.current_affiliation(affiliation.character_id).await?,
+                );
+            }
+
+            let mut corporations = BTreeMap::new();
+            let mut alliances = BTreeSet::new();
+            for affiliation in &affiliations {
+                corporations.insert(affiliation.corporation_id, affiliation.alliance_id);
+                if let Some(alliance_id) = affiliation.alliance_id {
+                    alliances.insert(alliance_id);
+                }
+            }
+            self.update_bulk_corporations(&corporations, &alliances)
+                .await?;
+
+            for affiliation in affiliations {
+                let previous = previous.remove(&affiliation.character_id).flatten();
+                sqlx::query!(
+                    "UPDATE character SET corporation_id=$1 WHERE id=$2",
+                    affiliation.corporation_id,
+                    affiliation.character_id
+                )
+                .execute(self.db.as_ref())
+                .await?;
+
+                if let Some(previous) = previous {
+                    if self
+                        .record_change(
+                            affiliation.character_id,
+                            previous,
+                            (affiliation.corporation_id, affiliation.alliance_id),
+                        )
+                        .await?
+                    {
+                        changed += 1;
+                    }
+                }
+            }
+        }
+
+        Ok(changed)
+    }
+
+    /// Inserts or updates the corporations and alliances seen in a bulk affiliation lookup.
+    /// Only names we don't know yet are looked up.
+    async fn update_bulk_corporations(
+        &self,
+        corporations: &BTreeMap<i64, Option<i64>>,
+        alliances: &BTreeSet<i64>,
+    ) -> Result<(), Madness> {
+        let corporation_ids: Vec<i64> = corporations.keys().copied().collect();
+        let alliance_ids: Vec<i64> = alliances.iter().copied().collect();
+
+        let known_corporations: BTreeSet<i64> = sqlx::query!(
+            "SELECT id FROM corporation WHERE id = ANY($1)",
+            &corporation_ids
+        )
+        .fetch_all(self.db.as_ref())
+        .await?
+        .into_iter()
+        .map(|corporation| corporation.id)
+        .collect();
+        let known_alliances: BTreeSet<i64> = sqlx::query!(
+            "SELECT id FROM alliance WHERE id = ANY($1)",
+            &alliance_ids
+        )
+        .fetch_all(self.db.as_ref())
+        .await?
+        .into_iter()
+        .map(|alliance| alliance.id)
+        .collect();
+
+        let unknown: Vec<i64> = corporation_ids
+            .iter()
+            .filter(|id| !known_corporations.contains(id))
+            .chain(alliance_ids.iter().filter(|id| !known_alliances.contains(id)))
+            .copied()
+            .collect();
+        let mut names = BTreeMap::new();
+        for chunk in unknown.chunks(BULK_LIMIT) {
+            let resolved: Vec<NameResponse> = self
+                .esi_client
+                .post_unauthenticated("/latest/universe/names/", chunk)
+                .await?;
+            names.extend(resolved.into_iter().map(|name| (name.id, name.name)));
+        }
+
+        for alliance_id in alliance_ids {
+            if let Some(name) = names.get(&alliance_id) {
+                sqlx::query!(
+                    "INSERT INTO alliance (id, name) VALUES ($1, $2) ON CONFLICT (id) DO UPDATE SET name=excluded.name",
+                    alliance_id,
+                    name
+                )
+                .execute(self.db.as_ref())
+                .await?;
+            }
+        }
+
+        let now = chrono::Utc::now().timestamp();
+        for (corporation_id, alliance_id) in corporations {
+            if known_corporations.contains(corporation_id) {
+                sqlx::query!(
+                    "UPDATE corporation SET alliance_id=$1, updated_at=$2 WHERE id=$3",
+                    *alliance_id,
+                    now,
+                    *corporation_id
+                )
+                .execute(self.db.as_ref())
+                .await?;
+            } else if let Some(name) = names.get(corporation_id) {
+                sqlx::query!(
+                    "INSERT INTO corporation (id, name, alliance_id, updated_at) VALUES ($1, $2, $3, $4)",
+                    *corporation_id,
+                    name,
+                    *alliance_id,
+                    now
+                )
+                .execute(self.db.as_ref())
+                .await?;
+            }
+        }
+
         Ok(())
     }
 
+    /// The corporation and alliance we have on record for a character, None if the character is unknown
+    async fn current_affiliation(
+        &self,
+        id: i64,
+    ) -> Result<Option<(Option<i64>, Option<i64>)>, Madness> {
+        Ok(sqlx::query!(
+            "SELECT character.corporation_id, corporation.alliance_id AS \"alliance_id?\" FROM character
+            LEFT JOIN corporation ON corporation.id=character.corporation_id WHERE character.id=$1",
+            id
+        )
+        .fetch_optional(self.db.as_ref())
+        .await?
+        .map(|character| (character.corporation_id, character.alliance_id)))
+    }
+
+    async fn record_change(
+        &self,
+        character_id: i64,
+        (old_corporation_id, old_alliance_id): (Option<i64>, Option<i64>),
+        (corporation_id, alliance_id): (i64, Option<i64>),
+    ) -> Result<bool, Madness> {
+        // Characters first seen in a fleet have no corporation yet, that isn't a change
+        if old_corporation_id.is_none()
+            || (old_corporation_id == Some(corporation_id) && old_alliance_id == alliance_id)
+        {
+            return Ok(false);
+        }
+
+        sqlx::query!(
+            "INSERT INTO affiliation_history (character_id, old_corporation_id, old_alliance_id, corporation_id, alliance_id, changed_at)
+            VALUES ($1, $2, $3, $4, $5, $6)",
+            character_id,
+            old_corporation_id,
+            old_alliance_id,
+            corporation_id,
+            alliance_id,
+            chrono::Utc::now().timestamp()
+        )
+        .execute(self.db.as_ref())
+        .await?;
+
+        Ok(true)
+    }
+
     pub async fn update_corp_affiliation(&self, id: i64) -> Result<(), Madness> {
         let corporation = sqlx::query!("SELECT * FROM corporation WHERE id=$1", id)
             .fetch_optional(self.db.as_ref())
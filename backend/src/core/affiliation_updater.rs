@@ -0,0 +1,70 @@
+use std::sync::Arc;
+
+use crate::core::{affiliation::AffiliationService, esi};
+use crate::{config::Config, util::madness::Madness};
+
+/// Periodically refreshes the corporation and alliance of recently seen characters,
+/// so corporation and alliance bans apply to pilots who changed corporations since their last login
+pub struct AffiliationUpdater {
+    affiliation_service: AffiliationService,
+    db: Arc<crate::DB>,
+    config: Config,
+}
+
+impl AffiliationUpdater {
+    pub fn new(db: Arc<crate::DB>, config: Config) -> AffiliationUpdater {
+        AffiliationUpdater {
+            affiliation_service: AffiliationService::new(
+                db.clone(),
+                esi::ESIClient::new(
+                    db.clone(),
+                    config.esi.client_id.clone(),
+                    config.esi.client_secret.clone(),
+                ),
+            ),
+            db,
+            config,
+        }
+    }
+
+    pub fn start(self) {
+        tokio::spawn(async move {
+            self.run().await;
+        });
+    }
+
+    async fn run(self) {
+        loop {
+            if let Err(e) = self.run_once().await {
+                error!("Error in affiliation updater: {:#?}", e);
+            }
+
+            tokio::time::sleep(tokio::time::Duration::from_secs(
+                self.config.affiliation_updater.interval,
+            ))
+            .await;
+        }
+    }
+
+    fn get_db(&self) -> &crate::DB {
+        &self.db
+    }
+
+    async fn run_once(&self) -> Result<(), Madness> {
+        let since = chrono::Utc::now().timestamp() - 86400 * self.config.affiliation_updater.seen_days;
+        let ids: Vec<i64> = sqlx::query!("SELECT id FROM character WHERE last_seen > $1", since)
+            .fetch_all(self.get_db())
+            .await?
+            .into_iter()
+            .map(|character| character.id)
+            .collect();
+
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        self.affiliation_service.update_affiliations(&ids).await?;
+
+        Ok(())
+    }
+}
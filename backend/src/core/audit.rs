@@ -0,0 +1,55 @@
+use serde_json::Value;
+
+/// A privileged action, written to the `audit_log` table.
+/// `before` and `after` hold the state of the target as JSON, when there is one.
+pub struct AuditEntry<'a> {
+    pub actor_id: i64,
+    pub action: &'a str,
+    pub target_type: &'a str,
+    pub target_id: Option<i64>,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+impl<'a> AuditEntry<'a> {
+    pub fn new(actor_id: i64, action: &'a str, target_type: &'a str, target_id: Option<i64>) -> Self {
+        AuditEntry {
+            actor_id,
+            action,
+            target_type,
+            target_id,
+            before: None,
+            after: None,
+        }
+    }
+
+    pub fn before(mut self, before: Value) -> Self {
+        self.before = Some(before);
+        self
+    }
+
+    pub fn after(mut self, after: Value) -> Self {
+        self.after = Some(after);
+        self
+    }
+}
+
+pub async fn record<'c, E>(executor: E, entry: AuditEntry<'_>) -> Result<(), sqlx::Error>
+where
+    E: sqlx::Executor<'c, Database = sqlx::Postgres>,
+{
+    sqlx::query!(
+        "INSERT INTO audit_log (actor_id, action, target_type, target_id, before, after, logged_at) VALUES ($1, $2, $3, $4, $5, $6, $7)",
+        entry.actor_id,
+        entry.action,
+        entry.target_type,
+        entry.target_id,
+        entry.before.map(|before| before.to_string()),
+        entry.after.map(|after| after.to_string()),
+        chrono::Utc::now().timestamp()
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
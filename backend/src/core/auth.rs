@@ -1,23 +1,24 @@
 use branca::Branca;
 use rocket::{
-    http::{Header, Status},
+    http::{Cookie, Header, Status},
     request::{FromRequest, Outcome, Request},
     Response,
 };
 use serde::{Deserialize, Serialize};
-use std::collections::{BTreeMap, BTreeSet};
+use std::collections::BTreeSet;
 
 static COOKIE_NAME: &str = "authToken";
 
-lazy_static::lazy_static! {
-    static ref ACCESS_LEVELS: BTreeMap<String, BTreeSet<String>> = build_access_levels();
-}
-
 pub struct AuthenticatedAccount {
     pub id: i64,
-    pub access: &'static std::collections::BTreeSet<String>,
+    pub access: BTreeSet<String>,
+    /// The login session the request was made with, None for API tokens
+    pub session_id: Option<i64>,
 }
 
+/// The User-Agent header of a request, stored with new sessions
+pub struct UserAgent(pub Option<String>);
+
 #[derive(Debug)]
 pub enum AuthenticationError {
     MissingCookie,
@@ -35,6 +36,26 @@ pub enum AuthorizationError {
 struct AuthToken {
     version: i32,
     account_id: i64,
+    /// Version 1 tokens were issued before sessions existed and have none
+    #[serde(default)]
+    session_id: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ApiToken {
+    version: i32,
+    token_id: i64,
+}
+
+fn cookie_value(token: &str, secure: bool) -> String {
+    let mut cookie = format!(
+        "{}={}; Path=/; HttpOnly; SameSite=Strict; Max-Age=2678400",
+        COOKIE_NAME, token
+    );
+    if secure {
+        cookie += "; Secure";
+    }
+    cookie
 }
 
 pub struct CookieSetter(pub String, pub bool);
@@ -42,14 +63,7 @@ impl<'r> rocket::response::Responder<'r, 'static> for CookieSetter {
     fn respond_to(self, _: &'r rocket::request::Request<'_>) -> rocket::response::Result<'static> {
         // XXX: Secure is set via a parameter in CookieSetter, but we can get this from the App
         let mut response = Response::new();
-        let mut cookie = format!(
-            "{}={}; Path=/; HttpOnly; SameSite=Strict; Max-Age=2678400",
-            COOKIE_NAME, self.0
-        );
-        if self.1 {
-            cookie += "; Secure";
-        }
-        response.set_header(Header::new("Set-Cookie", cookie));
+        response.set_header(Header::new("Set-Cookie", cookie_value(&self.0, self.1)));
         Ok(response)
     }
 }
@@ -66,24 +80,138 @@ fn decode_token(token: &str, secret: &[u8]) -> Result<AuthToken, AuthenticationE
         Ok(d) => d,
     };
 
-    if decoded.version != 1 || decoded.account_id <= 0 {
+    let valid = match decoded.version {
+        1 => decoded.session_id.is_none(),
+        2 => decoded.session_id.is_some(),
+        _ => false,
+    };
+    if !valid || decoded.account_id <= 0 {
         return Err(AuthenticationError::InvalidToken);
     }
 
     Ok(decoded)
 }
 
-pub fn create_cookie(app: &crate::app::Application, account_id: i64) -> CookieSetter {
+/// Starts a new login session and returns the cookie referencing it
+pub async fn create_session(
+    app: &crate::app::Application,
+    account_id: i64,
+    user_agent: Option<&str>,
+) -> Result<CookieSetter, sqlx::Error> {
+    let (_session_id, token) = start_session(app, account_id, user_agent).await?;
+    Ok(CookieSetter(
+        token,
+        app.config.esi.url.starts_with("https:"),
+    ))
+}
+
+async fn start_session(
+    app: &crate::app::Application,
+    account_id: i64,
+    user_agent: Option<&str>,
+) -> Result<(i64, String), sqlx::Error> {
+    let now = chrono::Utc::now().timestamp();
+
+    // Sessions outlive their cookie, clean up the ones that can't be used anymore
+    sqlx::query!(
+        "DELETE FROM session WHERE created_at < $1",
+        now - 31 * 86400
+    )
+    .execute(app.get_db())
+    .await?;
+
+    let session = sqlx::query!(
+        "INSERT INTO session (character_id, created_at, last_seen, user_agent) VALUES ($1, $2, $2, $3) RETURNING id",
+        account_id,
+        now,
+        user_agent
+    )
+    .fetch_one(app.get_db())
+    .await?;
+
     let mut branca = Branca::new(&app.token_secret).unwrap();
 
     let token = AuthToken {
-        version: 1,
+        version: 2,
         account_id,
+        session_id: Some(session.id),
     };
 
     let payload = rmp_serde::to_vec_named(&token).unwrap();
-    let encoded = branca.encode(&payload).unwrap();
-    CookieSetter(encoded, app.config.esi.url.starts_with("https:"))
+    Ok((session.id, branca.encode(&payload).unwrap()))
+}
+
+/// Logs an account out everywhere, e.g. when a role is removed
+pub async fn revoke_sessions(db: &crate::DB, account_id: i64) -> Result<(), sqlx::Error> {
+    sqlx::query!("DELETE FROM session WHERE character_id=$1", account_id)
+        .execute(db)
+        .await?;
+
+    Ok(())
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for UserAgent {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(UserAgent(
+            req.headers().get_one("User-Agent").map(|ua| ua.to_string()),
+        ))
+    }
+}
+
+/// API tokens don't expire on their own, their expiry and revocation are tracked in the `api_token` table
+pub fn create_api_token(secret: &[u8], token_id: i64) -> String {
+    let mut branca = Branca::new(secret).unwrap();
+    let token = ApiToken {
+        version: 1,
+        token_id,
+    };
+
+    let payload = rmp_serde::to_vec_named(&token).unwrap();
+    branca.encode(&payload).unwrap()
+}
+
+/// Returns the account an API token belongs to and the access keys the token was limited to
+async fn decode_api_token(
+    app: &crate::app::Application,
+    token: &str,
+) -> Result<(i64, BTreeSet<String>), AuthenticationError> {
+    let branca = Branca::new(&app.token_secret).unwrap();
+    let payload = match branca.decode(token, 0) {
+        Err(_) => return Err(AuthenticationError::InvalidToken),
+        Ok(p) => p,
+    };
+
+    let decoded: ApiToken = match rmp_serde::from_read_ref(&payload) {
+        Err(_) => return Err(AuthenticationError::InvalidToken),
+        Ok(d) => d,
+    };
+
+    if decoded.version != 1 {
+        return Err(AuthenticationError::InvalidToken);
+    }
+
+    let now = chrono::Utc::now().timestamp();
+    let api_token = match sqlx::query!(
+        "UPDATE api_token SET last_used_at=$1 WHERE id=$2 AND (expires_at IS NULL OR expires_at > $1)
+        RETURNING character_id, scopes",
+        now,
+        decoded.token_id
+    )
+    .fetch_optional(app.get_db())
+    .await
+    {
+        Err(e) => return Err(AuthenticationError::DatabaseError(e)),
+        Ok(None) => return Err(AuthenticationError::InvalidToken),
+        Ok(Some(t)) => t,
+    };
+
+    Ok((
+        api_token.character_id,
+        api_token.scopes.split_whitespace().map(String::from).collect(),
+    ))
 }
 
 #[rocket::async_trait]
@@ -96,20 +224,69 @@ impl<'r> FromRequest<'r> for AuthenticatedAccount {
             .await
             .unwrap();
 
-        let token = match req.cookies().get(COOKIE_NAME) {
-            None => {
-                return Outcome::Failure((Status::Unauthorized, AuthenticationError::MissingCookie))
-            }
-            Some(t) => match decode_token(t.value(), &app.token_secret) {
-                Ok(d) => d,
+        // Bots and external tools send an API token instead of the cookie, any other
+        // Authorization header (like basic auth from a proxy) is left to the cookie
+        let bearer = req
+            .headers()
+            .get_one("Authorization")
+            .and_then(|header| header.strip_prefix("Bearer "));
+        let (account_id, token_scopes, session_id) = match bearer {
+            Some(bearer) => match decode_api_token(app, bearer.trim()).await {
+                Ok((account_id, scopes)) => (account_id, Some(scopes), None),
+                Err(AuthenticationError::DatabaseError(e)) => {
+                    return Outcome::Failure((
+                        Status::InternalServerError,
+                        AuthenticationError::DatabaseError(e),
+                    ))
+                }
                 Err(e) => return Outcome::Failure((Status::Unauthorized, e)),
             },
+            None => match req.cookies().get(COOKIE_NAME) {
+                None => {
+                    return Outcome::Failure((
+                        Status::Unauthorized,
+                        AuthenticationError::MissingCookie,
+                    ))
+                }
+                Some(t) => match decode_token(t.value(), &app.token_secret) {
+                    Ok(AuthToken {
+                        account_id,
+                        session_id: Some(session_id),
+                        ..
+                    }) => (account_id, None, Some(session_id)),
+                    // Cookies from before sessions existed get a session on their next request
+                    Ok(AuthToken { account_id, .. }) => {
+                        let user_agent = req.headers().get_one("User-Agent");
+                        match start_session(app, account_id, user_agent).await {
+                            Ok((session_id, token)) => {
+                                let secure = app.config.esi.url.starts_with("https:");
+                                if let Ok(cookie) = Cookie::parse(cookie_value(&token, secure)) {
+                                    req.cookies().add(cookie);
+                                }
+                                (account_id, None, Some(session_id))
+                            }
+                            Err(e) => {
+                                return Outcome::Failure((
+                                    Status::InternalServerError,
+                                    AuthenticationError::DatabaseError(e),
+                                ))
+                            }
+                        }
+                    }
+                    Err(e) => return Outcome::Failure((Status::Unauthorized, e)),
+                },
+            },
         };
 
-        let access_level =
-            match sqlx::query!("SELECT * FROM admin WHERE character_id=$1", token.account_id)
-                .fetch_optional(app.get_db())
-                .await
+        // The session must still exist, revoked sessions are deleted
+        if let Some(session_id) = session_id {
+            match sqlx::query!(
+                "SELECT last_seen FROM session WHERE id=$1 AND character_id=$2",
+                session_id,
+                account_id
+            )
+            .fetch_optional(app.get_db())
+            .await
             {
                 Err(e) => {
                     return Outcome::Failure((
@@ -117,25 +294,66 @@ impl<'r> FromRequest<'r> for AuthenticatedAccount {
                         AuthenticationError::DatabaseError(e),
                     ))
                 }
-                Ok(Some(r)) => r.role,
-                Ok(None) => "user".to_string(),
-            };
-
-        let access_keys = match ACCESS_LEVELS.get(&access_level) {
-            Some(l) => l,
-            None => {
-                return Outcome::Failure((Status::Unauthorized, AuthenticationError::InvalidToken))
+                Ok(None) => {
+                    return Outcome::Failure((
+                        Status::Unauthorized,
+                        AuthenticationError::InvalidToken,
+                    ))
+                }
+                // Only written once a minute, it's shown in the session list and not needed to the second
+                Ok(Some(session)) => {
+                    let now = chrono::Utc::now().timestamp();
+                    if session.last_seen < now - 60 {
+                        if let Err(e) = sqlx::query!(
+                            "UPDATE session SET last_seen=$1 WHERE id=$2",
+                            now,
+                            session_id
+                        )
+                        .execute(app.get_db())
+                        .await
+                        {
+                            return Outcome::Failure((
+                                Status::InternalServerError,
+                                AuthenticationError::DatabaseError(e),
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut access_keys = match get_access_keys(app.get_db(), account_id).await {
+            Err(e) => {
+                return Outcome::Failure((
+                    Status::InternalServerError,
+                    AuthenticationError::DatabaseError(e),
+                ))
             }
+            Ok(keys) => keys,
         };
 
+        // A token never grants more than its owner currently has
+        if let Some(scopes) = token_scopes {
+            access_keys.retain(|key| scopes.contains(key));
+        }
+
         Outcome::Success(AuthenticatedAccount {
-            id: token.account_id,
+            id: account_id,
             access: access_keys,
+            session_id,
         })
     }
 }
 
 impl AuthenticatedAccount {
+    /// Account management needs a browser session, so an API token can't extend itself
+    pub fn require_session(&self) -> Result<(), AuthorizationError> {
+        match self.session_id {
+            Some(_) => Ok(()),
+            None => Err(AuthorizationError::AccessDenied),
+        }
+    }
+
     pub fn require_access(&self, key: &'static str) -> Result<(), AuthorizationError> {
         match self.access.contains(key) {
             true => Ok(()),
@@ -155,95 +373,22 @@ impl AuthenticatedAccount {
     }
 }
 
-fn build_access_levels() -> BTreeMap<String, BTreeSet<String>> {
-    fn build_level(
-        working: &mut BTreeMap<String, BTreeSet<String>>,
-        source: &str,
-        dest: &str,
-        keys: Vec<&str>,
-    ) {
-        let mut level = working.get(source).cloned().unwrap();
-        for key in keys {
-            level.insert(key.to_string());
-        }
-        working.insert(dest.to_string(), level);
-    }
-
-    let mut result = BTreeMap::new();
-    result.insert("user".to_string(), BTreeSet::new());
-
-    build_level(
-        &mut result,
-        "user",
-        "Wiki Team",
-        vec![
-            "wiki-editor"
-        ],
-    );
-    build_level(
-        &mut result,
-        "user",
-        "Trainee",
-        vec![
-            "fleet-configure",
-            "fleet-invite",
-            "fleet-view",
-            "pilot-view",
-            "waitlist-view",
-            "waitlist-tag:TRAINEE",
-            "fit-view",
-            "skill-view",
-            "waitlist-manage"
-        ],
-    );
-    build_level(
-        &mut result,
-        "Trainee",
-        "FC",
-        vec![
-            "bans-manage",
-            "badges-manage",
-            "commanders-view",
-            "fleet-activity-view",
-            "fleet-history-view",
-            "fit-history-view",
-            "search",
-            "skill-history-view",
-            "waitlist-edit",
-            "stats-view",
-            "waitlist-tag:HQ-FC",
-            "notes-view",
-            "notes-add",
-        ],
-    );
-    build_level(
-        &mut result,
-        "FC",
-        "Instructor",
-        vec![
-            "commanders-manage",
-            "commanders-manage:Trainee",
-            "commanders-manage:FC",
-            "fleet-admin",
-            "reports-view"
-        ],
-    );
-    build_level(
-        &mut result,
-        "Instructor",
-        "Leadership",
-        vec![
-            "commanders-manage:Wiki Team",
-            "commanders-manage:Instructor",
-            "commanders-manage:Leadership"
-        ],
-    );
-
-    result
-}
-
-pub fn get_access_keys(level: &str) -> Option<&'static BTreeSet<String>> {
-    ACCESS_LEVELS.get(level)
+/// The union of the access keys of every role granted to a character
+pub async fn get_access_keys(
+    db: &crate::DB,
+    character_id: i64,
+) -> Result<BTreeSet<String>, sqlx::Error> {
+    Ok(sqlx::query!(
+        "SELECT DISTINCT permission FROM role_permission
+        JOIN admin ON admin.role=role_permission.role WHERE admin.character_id=$1
+        AND (admin.expires_at IS NULL OR admin.expires_at > EXTRACT(EPOCH FROM NOW()))",
+        character_id
+    )
+    .fetch_all(db)
+    .await?
+    .into_iter()
+    .map(|key| key.permission)
+    .collect())
 }
 
 pub async fn authorize_character(
@@ -1,3 +1,5 @@
+use branca::Branca;
+use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
 use crate::util::{
@@ -5,6 +7,39 @@ use crate::util::{
     types::{Ban, Character, Entity},
 };
 
+#[derive(Serialize, Deserialize)]
+struct AppealToken {
+    version: i32,
+    ban_id: i64,
+    character_id: i64,
+}
+
+/// Banned pilots can't log in, so the ban response hands them a token to submit an appeal with
+pub fn create_appeal_token(secret: &[u8], ban_id: i64, character_id: i64) -> String {
+    let mut branca = Branca::new(secret).unwrap();
+    let token = AppealToken {
+        version: 1,
+        ban_id,
+        character_id,
+    };
+
+    let payload = rmp_serde::to_vec_named(&token).unwrap();
+    branca.encode(&payload).unwrap()
+}
+
+/// Returns the ban and character IDs of a valid appeal token
+pub fn decode_appeal_token(secret: &[u8], token: &str) -> Option<(i64, i64)> {
+    let branca = Branca::new(secret).unwrap();
+    let payload = branca.decode(token, 7 * 86400).ok()?;
+    let decoded: AppealToken = rmp_serde::from_read_ref(&payload).ok()?;
+
+    if decoded.version != 1 {
+        return None;
+    }
+
+    Some((decoded.ban_id, decoded.character_id))
+}
+
 pub struct BanService {
     db: Arc<crate::DB>,
 }
@@ -17,6 +52,8 @@ impl BanService {
     pub async fn character_bans(&self, character_id: i64) -> Result<Option<Vec<Ban>>, Madness> {
         if let Some(bans) = self.active_bans(character_id, "Character").await? {
             Ok(Some(bans))
+        } else if let Some(bans) = self.account_bans(character_id).await? {
+            Ok(Some(bans))
         } else {
             if let Some(character) = sqlx::query!(
                 "SELECT corporation_id FROM character WHERE id=$1",
@@ -35,6 +72,45 @@ impl BanService {
         }
     }
 
+    /// Account bans are issued against the main, and cover the main as well as every alt linked to it.
+    /// Alts with an active role are not covered, an FC alt can be linked after the ban was issued.
+    pub async fn account_bans(&self, character_id: i64) -> Result<Option<Vec<Ban>>, Madness> {
+        if let Some(bans) = self.active_bans(character_id, "Account").await? {
+            return Ok(Some(bans));
+        }
+
+        if sqlx::query!(
+            "SELECT character_id FROM admin WHERE character_id=$1
+            AND (expires_at IS NULL OR expires_at > EXTRACT(EPOCH FROM NOW())) LIMIT 1",
+            character_id
+        )
+        .fetch_optional(self.db.as_ref())
+        .await?
+        .is_some()
+        {
+            return Ok(None);
+        }
+
+        let mut accounts = Vec::new();
+        for alt in sqlx::query!(
+            "SELECT account_id FROM alt_character WHERE alt_id=$1",
+            character_id
+        )
+        .fetch_all(self.db.as_ref())
+        .await?
+        {
+            accounts.push(alt.account_id);
+        }
+
+        for account_id in accounts {
+            if let Some(bans) = self.active_bans(account_id, "Account").await? {
+                return Ok(Some(bans));
+            }
+        }
+
+        Ok(None)
+    }
+
     pub async fn corporation_bans(&self, corporation_id: i64) -> Result<Option<Vec<Ban>>, Madness> {
         if let Some(bans) = self.active_bans(corporation_id, "Corporation").await? {
             Ok(Some(bans))
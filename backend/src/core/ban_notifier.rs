@@ -0,0 +1,89 @@
+use std::sync::Arc;
+
+use serde::Serialize;
+
+use crate::{config::Config, util::madness::Madness};
+
+use super::sse;
+
+#[derive(Debug, Serialize)]
+struct BanExpired {
+    id: i64,
+    entity_id: i64,
+    entity_name: Option<String>,
+    entity_type: String,
+}
+
+pub struct BanNotifier {
+    sse_client: sse::SSEClient,
+    db: Arc<crate::DB>,
+}
+
+impl BanNotifier {
+    pub fn new(db: Arc<crate::DB>, config: Config) -> BanNotifier {
+        BanNotifier {
+            sse_client: sse::SSEClient::new(
+                config.sse.url.clone(),
+                &hex::decode(&config.sse.secret).unwrap(),
+            ),
+            db,
+        }
+    }
+
+    pub fn start(self) {
+        tokio::spawn(async move {
+            self.run().await;
+        });
+    }
+
+    async fn run(self) {
+        loop {
+            if let Err(e) = self.run_once().await {
+                error!("Error in ban notifier: {:#?}", e);
+            }
+
+            tokio::time::sleep(tokio::time::Duration::from_secs(60)).await;
+        }
+    }
+
+    fn get_db(&self) -> &crate::DB {
+        &self.db
+    }
+
+    async fn run_once(&self) -> Result<(), Madness> {
+        let now = chrono::Utc::now().timestamp();
+
+        // Bans revoked by an FC have revoked_by set, only tell FCs about the ones that ran out
+        let expired = sqlx::query_as!(
+            BanExpired,
+            "SELECT id, entity_id, entity_name, entity_type FROM ban
+            WHERE revoked_at <= $1 AND revoked_by IS NULL AND expiry_notified=false",
+            now
+        )
+        .fetch_all(self.get_db())
+        .await?;
+
+        if expired.is_empty() {
+            return Ok(());
+        }
+
+        self.sse_client
+            .submit(
+                expired
+                    .iter()
+                    .map(|ban| sse::Event::new_json("bans", "ban_expired", ban))
+                    .collect(),
+            )
+            .await?;
+
+        let ids: Vec<i64> = expired.iter().map(|ban| ban.id).collect();
+        sqlx::query!(
+            "UPDATE ban SET expiry_notified=true WHERE id = ANY($1)",
+            &ids
+        )
+        .execute(self.get_db())
+        .await?;
+
+        Ok(())
+    }
+}
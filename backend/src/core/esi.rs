@@ -76,6 +76,8 @@ pub enum ESIError {
     NoToken,
     #[error("missing ESI scope")]
     MissingScope,
+    #[error("ESI token has been revoked")]
+    TokenRevoked,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -87,6 +89,7 @@ pub enum ESIScope {
     UI_OpenWindow_v1,
     Skills_ReadSkills_v1,
     Clones_ReadImplants_v1,
+    Fittings_ReadFittings_v1,
     Search_v1,
 }
 
@@ -100,9 +103,32 @@ impl ESIScope {
             UI_OpenWindow_v1 => "esi-ui.open_window.v1",
             Skills_ReadSkills_v1 => "esi-skills.read_skills.v1",
             Clones_ReadImplants_v1 => "esi-clones.read_implants.v1",
+            Fittings_ReadFittings_v1 => "esi-fittings.read_fittings.v1",
             Search_v1 => "esi-search.search_structures.v1",
         }
     }
+
+    /// Scopes requested from every pilot on login
+    pub fn pilot_scopes() -> Vec<ESIScope> {
+        use ESIScope::*;
+        vec![
+            PublicData,
+            Skills_ReadSkills_v1,
+            Clones_ReadImplants_v1,
+            Fittings_ReadFittings_v1,
+        ]
+    }
+
+    /// Additional scopes requested from FCs
+    pub fn fc_scopes() -> Vec<ESIScope> {
+        use ESIScope::*;
+        vec![
+            Fleets_ReadFleet_v1,
+            Fleets_WriteFleet_v1,
+            UI_OpenWindow_v1,
+            Search_v1,
+        ]
+    }
 }
 
 impl From<reqwest::Error> for ESIError {
@@ -154,13 +180,30 @@ impl ESIRawClient {
             },
             scope: scope_str,
         };
-        Ok(self
+        let response = self
             .http
             .post("https://login.eveonline.com/v2/oauth/token")
             .basic_auth(&self.client_id, Some(&self.client_secret))
             .form(&request)
             .send()
-            .await?
+            .await?;
+
+        // A revoked (or otherwise dead) refresh token is reported as invalid_grant
+        if response.status() == reqwest::StatusCode::BAD_REQUEST {
+            #[derive(Deserialize)]
+            struct OAuthErrorResponse {
+                error: String,
+            }
+
+            if let Ok(body) = response.json::<OAuthErrorResponse>().await {
+                if body.error == "invalid_grant" {
+                    return Err(ESIError::TokenRevoked);
+                }
+            }
+            return Err(ESIError::Status(400));
+        }
+
+        Ok(response
             .error_for_status()?
             .json::<OAuthTokenResponse>()
             .await?)
@@ -274,6 +317,15 @@ impl ESIRawClient {
         Self::log_response_error(response).await
     }
 
+    pub async fn post_unauthenticated<E: Serialize + ?Sized>(
+        &self,
+        url: &str,
+        input: &E,
+    ) -> Result<reqwest::Response, ESIError> {
+        let response = self.http.post(url).json(input).send().await?;
+        Self::log_response_error(response).await
+    }
+
     pub async fn put<E: Serialize + ?Sized>(
         &self,
         url: &str,
@@ -324,7 +376,7 @@ impl ESIClient {
                 .await
             {
                 Ok(r) => r,
-                Err(ESIError::Status(400)) => result,
+                Err(ESIError::Status(400) | ESIError::TokenRevoked) => result,
                 Err(e) => return Err(e),
             };
 
@@ -378,6 +430,43 @@ impl ESIClient {
         .execute(&mut tx)
         .await?;
 
+        sqlx::query!(
+            "DELETE FROM revoked_token WHERE character_id=$1",
+            auth.character_id
+        )
+        .execute(&mut tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn delete_tokens(&self, character_id: i64, revoked: bool) -> Result<(), sqlx::Error> {
+        let mut tx = self.db.begin().await?;
+        sqlx::query!(
+            "DELETE FROM access_token WHERE character_id=$1",
+            character_id
+        )
+        .execute(&mut tx)
+        .await?;
+        sqlx::query!(
+            "DELETE FROM refresh_token WHERE character_id=$1",
+            character_id
+        )
+        .execute(&mut tx)
+        .await?;
+
+        // Remember the revocation so the pilot can be asked to log in again
+        if revoked {
+            sqlx::query!(
+                "INSERT INTO revoked_token (character_id, revoked_at) VALUES ($1, $2) ON CONFLICT (character_id) DO NOTHING",
+                character_id,
+                chrono::Utc::now().timestamp()
+            )
+            .execute(&mut tx)
+            .await?;
+        }
         tx.commit().await?;
 
         Ok(())
@@ -399,6 +488,13 @@ impl ESIClient {
             }
         }
 
+        self.refresh_access_token(character_id).await
+    }
+
+    async fn refresh_access_token(
+        &self,
+        character_id: i64,
+    ) -> Result<(String, BTreeSet<String>), ESIError> {
         let refresh = match sqlx::query!(
             "SELECT * FROM refresh_token WHERE character_id=$1",
             character_id
@@ -421,25 +517,21 @@ impl ESIClient {
             .await
         {
             Ok(r) => r,
+            Err(ESIError::TokenRevoked) => {
+                warn!(
+                    "Deleting refresh token for character {} as it has been revoked",
+                    character_id
+                );
+                self.delete_tokens(character_id, true).await?;
+
+                return Err(ESIError::NoToken);
+            }
             Err(ESIError::Status(400)) => {
                 warn!(
                     "Deleting refresh token for character {} as it failed to be used: HTTP 400",
                     character_id
                 );
-                let mut tx = self.db.begin().await?;
-                sqlx::query!(
-                    "DELETE FROM access_token WHERE character_id=$1",
-                    character_id
-                )
-                .execute(&mut tx)
-                .await?;
-                sqlx::query!(
-                    "DELETE FROM refresh_token WHERE character_id=$1",
-                    character_id
-                )
-                .execute(&mut tx)
-                .await?;
-                tx.commit().await?;
+                self.delete_tokens(character_id, false).await?;
 
                 return Err(ESIError::NoToken);
             }
@@ -450,6 +542,13 @@ impl ESIClient {
         Ok((refreshed.access_token, refreshed.scopes))
     }
 
+    /// Scopes granted to the character. The refresh token is always used, since a revoked token
+    /// is only noticed once it is refreshed.
+    pub async fn token_scopes(&self, character_id: i64) -> Result<BTreeSet<String>, ESIError> {
+        let (_token, scopes) = self.refresh_access_token(character_id).await?;
+        Ok(scopes)
+    }
+
     async fn access_token(&self, character_id: i64, scope: ESIScope) -> Result<String, ESIError> {
         let (token, scopes) = self.access_token_raw(character_id).await?;
 
@@ -516,6 +615,15 @@ impl ESIClient {
         return Ok(self.raw.post::<E>(&url, input, &access_token).await?.json().await?);
     }
 
+    pub async fn post_unauthenticated<E: Serialize + ?Sized, D: serde::de::DeserializeOwned>(
+        &self,
+        path: &str,
+        input: &E,
+    ) -> Result<D, ESIError> {
+        let url = format!("https://esi.evetech.net{}", path);
+        Ok(self.raw.post_unauthenticated::<E>(&url, input).await?.json().await?)
+    }
+
     pub async fn put<E: Serialize + ?Sized>(
         &self,
         path: &str,
@@ -562,7 +670,7 @@ pub mod fleet_members {
     }
 }
 
-fn split_scopes(input: &str) -> BTreeSet<String> {
+pub fn split_scopes(input: &str) -> BTreeSet<String> {
     input
         .split(' ')
         .filter(|s| !s.is_empty())
@@ -0,0 +1,254 @@
+use std::{collections::HashMap, sync::Arc};
+
+use serde::Deserialize;
+
+use crate::core::{ban::BanService, esi};
+use crate::{config::Config, util::madness::Madness};
+
+#[derive(Debug, Deserialize)]
+struct CorporationHistoryEntry {
+    corporation_id: i64,
+    start_date: String,
+}
+
+/// Looks for signs that pilots on the waitlist are evading a ban, FCs see a POSSIBLE-EVASION tag for flagged pilots
+pub struct EvasionDetector {
+    esi_client: esi::ESIClient,
+    ban_service: BanService,
+    db: Arc<crate::DB>,
+    config: Config,
+    // When each pilot was last checked, so pilots sitting on the waitlist aren't looked up every run
+    checked_at: HashMap<i64, i64>,
+}
+
+impl EvasionDetector {
+    pub fn new(db: Arc<crate::DB>, config: Config) -> EvasionDetector {
+        EvasionDetector {
+            esi_client: esi::ESIClient::new(
+                db.clone(),
+                config.esi.client_id.clone(),
+                config.esi.client_secret.clone(),
+            ),
+            ban_service: BanService::new(db.clone()),
+            db,
+            config,
+            checked_at: HashMap::new(),
+        }
+    }
+
+    pub fn start(self) {
+        tokio::spawn(async move {
+            self.run().await;
+        });
+    }
+
+    async fn run(mut self) {
+        loop {
+            if let Err(e) = self.run_once().await {
+                error!("Error in evasion detector: {:#?}", e);
+            }
+
+            tokio::time::sleep(tokio::time::Duration::from_secs(120)).await;
+        }
+    }
+
+    fn get_db(&self) -> &crate::DB {
+        &self.db
+    }
+
+    async fn run_once(&mut self) -> Result<(), Madness> {
+        let now = chrono::Utc::now().timestamp();
+        let pilots = sqlx::query!("SELECT DISTINCT character_id FROM waitlist_entry_fit")
+            .fetch_all(self.get_db())
+            .await?;
+
+        // Flags are only shown on the waitlist, pilots who left are checked again when they return
+        sqlx::query!(
+            "DELETE FROM evasion_flag WHERE character_id NOT IN (SELECT character_id FROM waitlist_entry_fit)"
+        )
+        .execute(self.get_db())
+        .await?;
+
+        self.checked_at.retain(|character_id, checked_at| {
+            *checked_at > now - 3600
+                && pilots
+                    .iter()
+                    .any(|pilot| pilot.character_id == *character_id)
+        });
+        for pilot in pilots {
+            if self.checked_at.contains_key(&pilot.character_id) {
+                continue;
+            }
+
+            self.check_pilot(pilot.character_id).await?;
+            self.checked_at.insert(pilot.character_id, now);
+        }
+
+        Ok(())
+    }
+
+    async fn check_pilot(&self, character_id: i64) -> Result<(), Madness> {
+        let shared_account = match self.banned_linked_character(character_id).await? {
+            Some(banned_id) => {
+                let banned = sqlx::query!("SELECT name FROM character WHERE id=$1", banned_id)
+                    .fetch_one(self.get_db())
+                    .await?;
+                Some(format!(
+                    "Shares an account with banned character {}",
+                    banned.name
+                ))
+            }
+            None => None,
+        };
+        self.flag(character_id, "shared-account", shared_account.as_deref())
+            .await?;
+
+        let corp_hop = match self.recent_corp_hop(character_id).await {
+            Ok(corp_hop) => {
+                self.flag(character_id, "corp-hop", corp_hop.as_deref())
+                    .await?;
+                Some(corp_hop.is_some())
+            }
+            Err(e) => {
+                // Keep the flag as it is until ESI answers again
+                warn!(
+                    "Could not check corporation history for {}: {:#?}",
+                    character_id, e
+                );
+                None
+            }
+        };
+
+        // Notes alone aren't a sign of evasion, they only count for pilots already linked to a ban
+        let noted = match (shared_account.is_some(), corp_hop) {
+            (true, _) | (_, Some(true)) => {
+                let notes = sqlx::query!(
+                    "SELECT COUNT(*) AS \"count!\" FROM character_note WHERE character_id=$1",
+                    character_id
+                )
+                .fetch_one(self.get_db())
+                .await?;
+                match notes.count {
+                    0 => None,
+                    count => Some(format!("Has {} note(s) on record", count)),
+                }
+            }
+            (false, Some(false)) => None,
+            // Without the corporation history the flag is left as it is
+            (false, None) => return Ok(()),
+        };
+        self.flag(character_id, "noted", noted.as_deref()).await?;
+
+        Ok(())
+    }
+
+    /// Any character on the same account (as main or alt) with an active character or account ban
+    async fn banned_linked_character(&self, character_id: i64) -> Result<Option<i64>, Madness> {
+        let linked = sqlx::query!(
+            "SELECT account_id AS \"id!\" FROM alt_character WHERE alt_id=$1
+            UNION SELECT alt_id AS \"id!\" FROM alt_character WHERE account_id=$1
+            UNION SELECT alt_id AS \"id!\" FROM alt_character WHERE account_id IN (SELECT account_id FROM alt_character WHERE alt_id=$1)",
+            character_id
+        )
+        .fetch_all(self.get_db())
+        .await?;
+
+        for other in linked {
+            if other.id == character_id {
+                continue;
+            }
+            if self
+                .ban_service
+                .active_bans(other.id, "Character")
+                .await?
+                .is_some()
+                || self.ban_service.account_bans(other.id).await?.is_some()
+            {
+                return Ok(Some(other.id));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Did the pilot recently leave a corporation (or its alliance) that is banned
+    async fn recent_corp_hop(&self, character_id: i64) -> Result<Option<String>, Madness> {
+        let history: Vec<CorporationHistoryEntry> = self
+            .esi_client
+            .get_unauthenticated(&format!(
+                "/v2/characters/{}/corporationhistory/",
+                character_id
+            ))
+            .await?;
+
+        // ESI returns the most recent corporation first
+        let (current, previous) = match (history.get(0), history.get(1)) {
+            (Some(current), Some(previous)) => (current, previous),
+            _ => return Ok(None),
+        };
+
+        let joined_at = match chrono::DateTime::parse_from_rfc3339(&current.start_date) {
+            Ok(date) => date.timestamp(),
+            Err(_) => return Ok(None),
+        };
+        let cutoff =
+            chrono::Utc::now().timestamp() - self.config.evasion_detector.corp_hop_days * 86400;
+        if joined_at < cutoff {
+            return Ok(None);
+        }
+
+        if let Some(bans) = self
+            .ban_service
+            .corporation_bans(previous.corporation_id)
+            .await?
+        {
+            let entity = bans.first().and_then(|ban| ban.entity.as_ref());
+            return Ok(Some(format!(
+                "Left banned {} {} on {}",
+                entity
+                    .map(|e| e.category.to_lowercase())
+                    .unwrap_or_else(|| "corporation".to_string()),
+                entity
+                    .and_then(|e| e.name.clone())
+                    .unwrap_or_else(|| previous.corporation_id.to_string()),
+                current.start_date
+            )));
+        }
+
+        Ok(None)
+    }
+
+    /// Raises the flag while `details` are given and clears it once the condition no longer holds
+    async fn flag(
+        &self,
+        character_id: i64,
+        reason: &str,
+        details: Option<&str>,
+    ) -> Result<(), Madness> {
+        match details {
+            Some(details) => {
+                sqlx::query!(
+                    "INSERT INTO evasion_flag (character_id, reason, details, flagged_at) VALUES ($1, $2, $3, $4)
+                    ON CONFLICT (character_id, reason) DO UPDATE SET details=excluded.details",
+                    character_id,
+                    reason,
+                    details,
+                    chrono::Utc::now().timestamp()
+                )
+                .execute(self.get_db())
+                .await?;
+            }
+            None => {
+                sqlx::query!(
+                    "DELETE FROM evasion_flag WHERE character_id=$1 AND reason=$2",
+                    character_id,
+                    reason
+                )
+                .execute(self.get_db())
+                .await?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -1,4 +1,5 @@
 use crate::core::esi::{self, ESIScope};
+use crate::core::time_in_fleet::TimeInFleetService;
 use crate::data::character;
 use crate::{config::Config, util::madness::Madness};
 use serde::{Deserialize, Serialize};
@@ -121,9 +122,7 @@ impl FleetUpdater {
                     .execute(&mut tx)
                     .await?;
 
-                sqlx::query!("UPDATE fleet_activity SET last_seen=$1,has_left=true WHERE fleet_id=$2 AND has_left=false", now, fleet_id)
-                    .execute(&mut tx)
-                    .await?;
+                TimeInFleetService::close_fleet(&mut tx, fleet_id, now).await?;
 
                 tx.commit().await?;
 
@@ -277,6 +276,8 @@ impl FleetUpdater {
                                     .execute(&mut tx)
                                     .await?;
 
+                                TimeInFleetService::record(&mut tx, member.character_id, in_db.hull, in_db.is_boss, now - in_db.last_seen).await?;
+
                                 changed = true;
                             }
                         }
@@ -285,6 +286,8 @@ impl FleetUpdater {
                                 .execute(&mut tx)
                                 .await?;
 
+                            TimeInFleetService::record(&mut tx, member.character_id, in_db.hull, in_db.is_boss, now - in_db.last_seen).await?;
+
                             insert_record = true;
                         }
                     } else {
@@ -0,0 +1,250 @@
+use std::{collections::BTreeSet, sync::Arc};
+
+use eve_data_core::{Fitting, TypeDB, TypeID};
+use serde::Serialize;
+
+use crate::core::time_in_fleet::TimeInFleetService;
+use crate::data::skills;
+use crate::tdf::fitcheck::{FitChecker, PilotData};
+use crate::tdf::milestones::{self, Milestone};
+use crate::{config::Config, util::madness::Madness};
+
+use super::sse;
+
+#[derive(Debug, Serialize)]
+struct MilestoneNotification {
+    title: String,
+    message: String,
+}
+
+pub struct MilestoneNotifier {
+    sse_client: sse::SSEClient,
+    time_in_fleet: TimeInFleetService,
+    db: Arc<crate::DB>,
+    config: Config,
+}
+
+impl MilestoneNotifier {
+    pub fn new(db: Arc<crate::DB>, config: Config) -> MilestoneNotifier {
+        MilestoneNotifier {
+            sse_client: sse::SSEClient::new(
+                config.sse.url.clone(),
+                &hex::decode(&config.sse.secret).unwrap(),
+            ),
+            time_in_fleet: TimeInFleetService::new(db.clone()),
+            db,
+            config,
+        }
+    }
+
+    pub fn start(self) {
+        tokio::spawn(async move {
+            self.run().await;
+        });
+    }
+
+    async fn run(self) {
+        loop {
+            if let Err(e) = self.run_once().await {
+                error!("Error in milestone notifier: {:#?}", e);
+            }
+
+            tokio::time::sleep(tokio::time::Duration::from_secs(300)).await;
+        }
+    }
+
+    fn get_db(&self) -> &crate::DB {
+        &self.db
+    }
+
+    async fn run_once(&self) -> Result<(), Madness> {
+        // Only pilots who have been in fleet recently can have gained any hours
+        let since = chrono::Utc::now().timestamp() - 900;
+        let pilots = sqlx::query!(
+            "SELECT DISTINCT ON (character_id) character_id, hull FROM fleet_activity
+            WHERE last_seen > $1 ORDER BY character_id, last_seen DESC",
+            since
+        )
+        .fetch_all(self.get_db())
+        .await?;
+
+        for pilot in pilots {
+            self.check_pilot(pilot.character_id, pilot.hull as TypeID).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn check_pilot(&self, character_id: i64, hull: TypeID) -> Result<(), Madness> {
+        let time_in_fleet = self.time_in_fleet.character_time(character_id).await?.total;
+
+        // Judge the pilot on the last fit they x'd up with in this hull
+        let last_xup = sqlx::query!(
+            "SELECT dna, implants FROM fit_history
+            JOIN fitting ON fitting.id=fit_history.fit_id
+            JOIN implant_set ON implant_set.id=fit_history.implant_set_id
+            WHERE character_id=$1 AND fitting.hull=$2 ORDER BY logged_at DESC LIMIT 1",
+            character_id,
+            hull
+        )
+        .fetch_optional(self.get_db())
+        .await?;
+
+        let badges: Vec<String> = sqlx::query!(
+            "SELECT badge.name FROM badge JOIN badge_assignment ON id=badge_assignment.BadgeId WHERE badge_assignment.CharacterId=$1",
+            character_id
+        )
+        .fetch_all(self.get_db())
+        .await?
+        .into_iter()
+        .map(|badge| badge.name)
+        .collect();
+
+        let (modules, pilot_is_elite) = match last_xup {
+            Some(xup) => {
+                let fit = Fitting::from_dna(&xup.dna)?;
+                let implants: Vec<TypeID> = xup
+                    .implants
+                    .split(':')
+                    .filter_map(|i| i.parse().ok())
+                    .collect();
+                let skills = skills::load_known_skills(self.get_db(), character_id).await?;
+                let access_keys = BTreeSet::new();
+                let pilot = PilotData {
+                    implants: &implants,
+                    time_in_fleet,
+                    skills: &skills,
+                    access_keys: &access_keys,
+                    id: &character_id,
+                };
+
+                // Same ELITE rules as the fit checker uses when x'ing up
+                let pilot_is_elite = FitChecker::is_elite(&pilot, &fit, &badges)?;
+                (fit.modules, pilot_is_elite)
+            }
+            None => (Default::default(), false),
+        };
+
+        if let Some(tag) = milestones::check(hull, &modules, time_in_fleet, pilot_is_elite, &badges) {
+            let reached = milestones::milestones(hull)
+                .into_iter()
+                .filter(|milestone| time_in_fleet >= milestone.hours * 3600)
+                .last();
+
+            if let Some(milestone) = reached {
+                self.record(character_id, hull, Milestone { tag, ..milestone }, "reached").await?;
+            }
+        } else {
+            // Every milestone reached so far is met, nothing is pending for them any more
+            sqlx::query!(
+                "DELETE FROM pending_upgrade WHERE character_id=$1 AND hull=$2 AND hours * 3600 <= $3",
+                character_id,
+                hull,
+                time_in_fleet
+            )
+            .execute(self.get_db())
+            .await?;
+
+            if let Some(milestone) = milestones::next_milestone(hull, time_in_fleet) {
+                let warn_at = (milestone.hours - self.config.milestone_notifier.warn_hours) * 3600;
+                let would_fail = milestones::check(
+                    hull,
+                    &modules,
+                    milestone.hours * 3600,
+                    pilot_is_elite,
+                    &badges,
+                )
+                .is_some();
+
+                if !would_fail {
+                    // Already upgraded ahead of the milestone
+                    sqlx::query!(
+                        "DELETE FROM pending_upgrade WHERE character_id=$1 AND hull=$2 AND hours=$3",
+                        character_id,
+                        hull,
+                        milestone.hours
+                    )
+                    .execute(self.get_db())
+                    .await?;
+                } else if time_in_fleet >= warn_at {
+                    self.record(character_id, hull, milestone, "approaching").await?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn record(
+        &self,
+        character_id: i64,
+        hull: TypeID,
+        milestone: Milestone,
+        state: &str,
+    ) -> Result<(), Madness> {
+        let now = chrono::Utc::now().timestamp();
+
+        // Every milestone is only announced once
+        let inserted = sqlx::query!(
+            "INSERT INTO pending_upgrade (character_id, hull, hours, tag, state, logged_at) VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (character_id, hull, hours, state) DO NOTHING RETURNING id",
+            character_id,
+            hull,
+            milestone.hours,
+            milestone.tag,
+            state,
+            now
+        )
+        .fetch_optional(self.get_db())
+        .await?;
+
+        if inserted.is_none() {
+            return Ok(());
+        }
+
+        let hull_name = TypeDB::name_of(hull)?;
+        let character = sqlx::query!("SELECT name FROM character WHERE id=$1", character_id)
+            .fetch_one(self.get_db())
+            .await?;
+
+        let notification = if state == "reached" {
+            MilestoneNotification {
+                title: "Upgrade required".to_string(),
+                message: format!(
+                    "{} has reached {} hours in fleet. Your {} will not be approved until you meet the requirements for {}.",
+                    character.name, milestone.hours, hull_name, milestone.tag
+                ),
+            }
+        } else {
+            MilestoneNotification {
+                title: "Upgrade coming up".to_string(),
+                message: format!(
+                    "{} is approaching {} hours in fleet. Plan your upgrade, at that point your {} will no longer be approved.",
+                    character.name, milestone.hours, hull_name
+                ),
+            }
+        };
+
+        // Notify the character's own account as well as every account it is linked to as an alt
+        let mut accounts = vec![character_id];
+        accounts.extend(
+            sqlx::query!("SELECT account_id FROM alt_character WHERE alt_id=$1", character_id)
+                .fetch_all(self.get_db())
+                .await?
+                .into_iter()
+                .map(|alt| alt.account_id),
+        );
+
+        let topics: Vec<String> = accounts
+            .into_iter()
+            .map(|account_id| format!("account;{}", account_id))
+            .collect();
+        let events = topics
+            .iter()
+            .map(|topic| sse::Event::new_json(topic, "message", &notification))
+            .collect();
+        self.sse_client.submit(events).await?;
+
+        Ok(())
+    }
+}
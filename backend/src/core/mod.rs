@@ -1,7 +1,16 @@
 pub mod affiliation;
+pub mod affiliation_updater;
+pub mod audit;
 pub mod auth;
 pub mod ban;
+pub mod ban_notifier;
 pub mod esi;
+pub mod evasion;
 pub mod fleet_updater;
+pub mod milestone_notifier;
+pub mod role_expiry;
+pub mod scope_checker;
 pub mod skill_updater;
 pub mod sse;
+pub mod time_in_fleet;
+pub mod webhook;
@@ -0,0 +1,128 @@
+use std::sync::Arc;
+
+use serde::Serialize;
+
+use crate::{config::Config, util::madness::Madness};
+
+use super::sse;
+
+#[derive(Debug, Serialize)]
+struct ExpiryNotification {
+    title: String,
+    message: String,
+}
+
+/// Revokes time limited role grants once they expire, and warns whoever granted them beforehand
+pub struct RoleExpiry {
+    sse_client: sse::SSEClient,
+    db: Arc<crate::DB>,
+    config: Config,
+}
+
+impl RoleExpiry {
+    pub fn new(db: Arc<crate::DB>, config: Config) -> RoleExpiry {
+        RoleExpiry {
+            sse_client: sse::SSEClient::new(
+                config.sse.url.clone(),
+                &hex::decode(&config.sse.secret).unwrap(),
+            ),
+            db,
+            config,
+        }
+    }
+
+    pub fn start(self) {
+        tokio::spawn(async move {
+            self.run().await;
+        });
+    }
+
+    async fn run(self) {
+        loop {
+            if let Err(e) = self.run_once().await {
+                error!("Error in role expiry: {:#?}", e);
+            }
+
+            tokio::time::sleep(tokio::time::Duration::from_secs(60)).await;
+        }
+    }
+
+    fn get_db(&self) -> &crate::DB {
+        &self.db
+    }
+
+    async fn run_once(&self) -> Result<(), Madness> {
+        self.warn_expiring().await?;
+        self.revoke_expired().await?;
+        Ok(())
+    }
+
+    async fn warn_expiring(&self) -> Result<(), Madness> {
+        let warn_before = chrono::Utc::now().timestamp() + self.config.role_expiry.warn_hours * 3600;
+
+        let expiring = sqlx::query!(
+            "SELECT character_id, role, granted_by_id, expires_at AS \"expires_at!\", character.name FROM admin
+            JOIN character ON character.id=admin.character_id
+            WHERE expires_at <= $1 AND expiry_warned=false",
+            warn_before
+        )
+        .fetch_all(self.get_db())
+        .await?;
+
+        for grant in expiring {
+            let expires_in = (grant.expires_at - chrono::Utc::now().timestamp()).max(0) / 3600;
+            let notification = ExpiryNotification {
+                title: "Role expiring".to_string(),
+                message: format!(
+                    "The {} role you granted to {} expires in {} hours.",
+                    grant.role, grant.name, expires_in
+                ),
+            };
+
+            let topic = format!("account;{}", grant.granted_by_id);
+            self.sse_client
+                .submit(vec![sse::Event::new_json(&topic, "message", &notification)])
+                .await?;
+
+            sqlx::query!(
+                "UPDATE admin SET expiry_warned=true WHERE character_id=$1 AND role=$2",
+                grant.character_id,
+                grant.role
+            )
+            .execute(self.get_db())
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn revoke_expired(&self) -> Result<(), Madness> {
+        let now = chrono::Utc::now().timestamp();
+        let mut tx = self.get_db().begin().await?;
+
+        // Keep a record of the grant, the admin row is gone afterwards
+        sqlx::query!(
+            "INSERT INTO role_expiry_log (character_id, role, granted_by_id, granted_at, reason, expired_at)
+            SELECT character_id, role, granted_by_id, granted_at, reason, expires_at FROM admin WHERE expires_at <= $1",
+            now
+        )
+        .execute(&mut tx)
+        .await?;
+
+        // Browsers logged in with the expired role have to log in again
+        sqlx::query!(
+            "DELETE FROM session WHERE character_id IN (SELECT character_id FROM admin WHERE expires_at <= $1)",
+            now
+        )
+        .execute(&mut tx)
+        .await?;
+
+        sqlx::query!("DELETE FROM admin WHERE expires_at <= $1", now)
+            .execute(&mut tx)
+            .await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+}
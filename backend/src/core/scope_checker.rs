@@ -0,0 +1,235 @@
+use std::sync::Arc;
+
+use serde::Serialize;
+
+use crate::core::auth::get_access_keys;
+use crate::core::esi::{self, ESIError, ESIScope};
+use crate::util::types;
+use crate::{config::Config, util::madness::Madness};
+
+use super::sse;
+
+#[derive(Serialize)]
+pub struct ScopeHealth {
+    character: types::Character,
+    token_valid: bool,
+    granted: Vec<String>,
+    required: Vec<&'static str>,
+    missing: Vec<&'static str>,
+    /// Set when ESI could not be asked about the token during the last check
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ReauthPrompt<'a> {
+    characters: Vec<&'a types::Character>,
+}
+
+/// Scope health of an account and its alts, as of the last token check
+pub async fn account_health(db: &crate::DB, account_id: i64) -> Result<Vec<ScopeHealth>, Madness> {
+    let characters = sqlx::query!(
+        "SELECT character.id, character.name, refresh_token.scopes AS \"scopes?\", token_check.error AS \"error?\"
+        FROM character
+        LEFT JOIN refresh_token ON refresh_token.character_id=character.id
+        LEFT JOIN token_check ON token_check.character_id=character.id
+        WHERE character.id=$1 OR character.id IN (SELECT alt_id FROM alt_character WHERE account_id=$1)
+        ORDER BY character.name ASC",
+        account_id
+    )
+    .fetch_all(db)
+    .await?;
+
+    let mut health = Vec::new();
+    for character in characters {
+        // FCs need the fleet scopes on top of the ones every pilot grants
+        let mut required = ESIScope::pilot_scopes();
+        if get_access_keys(db, character.id)
+            .await?
+            .contains("fleet-configure")
+        {
+            required.extend(ESIScope::fc_scopes());
+        }
+
+        let granted = character
+            .scopes
+            .as_deref()
+            .map(esi::split_scopes)
+            .unwrap_or_default();
+        let required: Vec<&'static str> = required.into_iter().map(|scope| scope.as_str()).collect();
+        let missing = match character.error {
+            Some(_) => Vec::new(),
+            None => required
+                .iter()
+                .copied()
+                .filter(|scope| !granted.contains(*scope))
+                .collect(),
+        };
+
+        health.push(ScopeHealth {
+            character: types::Character {
+                id: character.id,
+                name: character.name,
+                corporation_id: None,
+            },
+            token_valid: character.scopes.is_some(),
+            granted: granted.into_iter().collect(),
+            required,
+            missing,
+            error: character.error,
+        });
+    }
+
+    Ok(health)
+}
+
+/// Uses the refresh tokens of recently seen accounts, so revoked tokens and missing scopes are
+/// noticed and the pilot is asked to log in again while they are still around
+pub struct ScopeChecker {
+    esi_client: esi::ESIClient,
+    sse_client: sse::SSEClient,
+    db: Arc<crate::DB>,
+    config: Config,
+}
+
+impl ScopeChecker {
+    pub fn new(db: Arc<crate::DB>, config: Config) -> ScopeChecker {
+        ScopeChecker {
+            esi_client: esi::ESIClient::new(
+                db.clone(),
+                config.esi.client_id.clone(),
+                config.esi.client_secret.clone(),
+            ),
+            sse_client: sse::SSEClient::new(
+                config.sse.url.clone(),
+                &hex::decode(&config.sse.secret).unwrap(),
+            ),
+            db,
+            config,
+        }
+    }
+
+    pub fn start(self) {
+        tokio::spawn(async move {
+            self.run().await;
+        });
+    }
+
+    async fn run(self) {
+        loop {
+            if let Err(e) = self.run_once().await {
+                error!("Error in scope checker: {:#?}", e);
+            }
+
+            tokio::time::sleep(tokio::time::Duration::from_secs(
+                self.config.scope_checker.interval,
+            ))
+            .await;
+        }
+    }
+
+    fn get_db(&self) -> &crate::DB {
+        &self.db
+    }
+
+    async fn run_once(&self) -> Result<(), Madness> {
+        let since = chrono::Utc::now().timestamp() - 86400;
+        let accounts = sqlx::query!(
+            "SELECT id FROM character WHERE last_seen > $1 AND id NOT IN (SELECT alt_id FROM alt_character)",
+            since
+        )
+        .fetch_all(self.get_db())
+        .await?;
+
+        for account in accounts {
+            self.check_account(account.id).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn check_account(&self, account_id: i64) -> Result<(), Madness> {
+        let characters = sqlx::query!(
+            "SELECT character_id FROM refresh_token
+            WHERE character_id=$1 OR character_id IN (SELECT alt_id FROM alt_character WHERE account_id=$1)",
+            account_id
+        )
+        .fetch_all(self.get_db())
+        .await?;
+
+        for character in characters {
+            let error = match self.esi_client.token_scopes(character.character_id).await {
+                // A revoked token is deleted, the pilot shows up without one
+                Ok(_) | Err(ESIError::NoToken) => None,
+                Err(e) => {
+                    warn!("Could not check the token of {}: {}", character.character_id, e);
+                    Some(e.to_string())
+                }
+            };
+
+            sqlx::query!(
+                "INSERT INTO token_check (character_id, checked_at, error) VALUES ($1, $2, $3)
+                ON CONFLICT (character_id) DO UPDATE SET checked_at=excluded.checked_at, error=excluded.error",
+                character.character_id,
+                chrono::Utc::now().timestamp(),
+                error
+            )
+            .execute(self.get_db())
+            .await?;
+        }
+
+        let health = account_health(self.get_db(), account_id).await?;
+        let needs_reauth: Vec<&ScopeHealth> = health
+            .iter()
+            .filter(|h| h.error.is_none() && (!h.token_valid || !h.missing.is_empty()))
+            .collect();
+
+        // Only prompt again when the characters or scopes that need a re-auth changed
+        let mut summary: Vec<String> = needs_reauth
+            .iter()
+            .map(|h| format!("{}:{}", h.character.id, h.missing.join(",")))
+            .collect();
+        summary.sort();
+        let summary = summary.join(";");
+
+        let previous = sqlx::query!(
+            "SELECT missing FROM reauth_prompt WHERE account_id=$1",
+            account_id
+        )
+        .fetch_optional(self.get_db())
+        .await?
+        .map(|prompt| prompt.missing)
+        .unwrap_or_default();
+
+        if summary == previous {
+            return Ok(());
+        }
+
+        if summary.is_empty() {
+            sqlx::query!("DELETE FROM reauth_prompt WHERE account_id=$1", account_id)
+                .execute(self.get_db())
+                .await?;
+            return Ok(());
+        }
+
+        sqlx::query!(
+            "INSERT INTO reauth_prompt (account_id, missing) VALUES ($1, $2)
+            ON CONFLICT (account_id) DO UPDATE SET missing=excluded.missing",
+            account_id,
+            summary
+        )
+        .execute(self.get_db())
+        .await?;
+
+        self.sse_client
+            .submit(vec![sse::Event::new_json(
+                &format!("account;{}", account_id),
+                "reauth",
+                &ReauthPrompt {
+                    characters: needs_reauth.iter().map(|h| &h.character).collect(),
+                },
+            )])
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,168 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use eve_data_core::{Fitting, TypeID};
+use serde::Serialize;
+
+use crate::data::categories;
+
+/// Accumulated time in fleet, in seconds.
+#[derive(Debug, Default, Serialize)]
+pub struct FleetTime {
+    pub total: i64,
+    pub by_hull: BTreeMap<TypeID, i64>,
+    pub by_category: BTreeMap<String, i64>,
+}
+
+impl FleetTime {
+    fn add(&mut self, hull: TypeID, seconds: i64) {
+        self.total += seconds;
+        *self.by_hull.entry(hull).or_insert(0) += seconds;
+        *self
+            .by_category
+            .entry(hull_category(hull))
+            .or_insert(0) += seconds;
+    }
+
+    pub fn hull_seconds(&self, hulls: &[TypeID]) -> i64 {
+        hulls
+            .iter()
+            .map(|hull| self.by_hull.get(hull).copied().unwrap_or(0))
+            .sum()
+    }
+}
+
+/// Categorize a hull using the same rules as the waitlist, ignoring modules
+pub fn hull_category(hull: TypeID) -> String {
+    let fit = Fitting {
+        hull,
+        modules: BTreeMap::new(),
+        cargo: BTreeMap::new(),
+    };
+    categories::categorize(&fit).unwrap_or_else(|| "other".to_string())
+}
+
+/// Keeps the per character totals in `fleet_time` in sync with `fleet_activity`.
+/// Account totals are rolled up from the character totals of the account and its alts,
+/// so linking or unlinking an alt never leaves them stale.
+pub struct TimeInFleetService {
+    db: Arc<crate::DB>,
+}
+
+impl TimeInFleetService {
+    pub fn new(database: Arc<crate::DB>) -> TimeInFleetService {
+        TimeInFleetService { db: database }
+    }
+
+    /// Adds time to a character's total. Called whenever a fleet_activity row's last_seen moves forward.
+    pub async fn record(
+        tx: &mut crate::DBTX<'_>,
+        character_id: i64,
+        hull: TypeID,
+        is_boss: bool,
+        seconds: i64,
+    ) -> Result<(), sqlx::Error> {
+        if seconds <= 0 {
+            return Ok(());
+        }
+
+        sqlx::query!(
+            "INSERT INTO fleet_time (character_id, hull, seconds, boss_seconds) VALUES ($1, $2, $3, $4)
+            ON CONFLICT (character_id, hull) DO UPDATE SET seconds = fleet_time.seconds + excluded.seconds,
+            boss_seconds = fleet_time.boss_seconds + excluded.boss_seconds",
+            character_id,
+            hull,
+            seconds,
+            if is_boss { seconds } else { 0 }
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Closes every open fleet_activity row of a fleet, crediting the time since they were last seen
+    pub async fn close_fleet(
+        tx: &mut crate::DBTX<'_>,
+        fleet_id: i64,
+        now: i64,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "INSERT INTO fleet_time (character_id, hull, seconds, boss_seconds)
+            SELECT character_id, hull, CAST(SUM($1 - last_seen) AS BIGINT),
+            CAST(SUM(CASE WHEN is_boss THEN $1 - last_seen ELSE 0 END) AS BIGINT) FROM fleet_activity
+            WHERE fleet_id=$2 AND has_left=false AND last_seen < $1
+            GROUP BY character_id, hull
+            ON CONFLICT (character_id, hull) DO UPDATE SET seconds = fleet_time.seconds + excluded.seconds,
+            boss_seconds = fleet_time.boss_seconds + excluded.boss_seconds",
+            now,
+            fleet_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            "UPDATE fleet_activity SET last_seen=$1,has_left=true WHERE fleet_id=$2 AND has_left=false",
+            now,
+            fleet_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn character_time(&self, character_id: i64) -> Result<FleetTime, sqlx::Error> {
+        let rows = sqlx::query!(
+            "SELECT hull, seconds FROM fleet_time WHERE character_id=$1",
+            character_id
+        )
+        .fetch_all(self.db.as_ref())
+        .await?;
+
+        let mut time = FleetTime::default();
+        for row in rows {
+            time.add(row.hull as TypeID, row.seconds);
+        }
+        Ok(time)
+    }
+
+    /// Time in fleet for the account and all of its linked alts
+    pub async fn account_time(&self, account_id: i64) -> Result<FleetTime, sqlx::Error> {
+        let rows = sqlx::query!(
+            "SELECT hull, CAST(SUM(seconds) AS BIGINT) AS \"seconds!\" FROM fleet_time
+            WHERE character_id=$1 OR character_id IN (SELECT alt_id FROM alt_character WHERE account_id=$1)
+            GROUP BY hull",
+            account_id
+        )
+        .fetch_all(self.db.as_ref())
+        .await?;
+
+        let mut time = FleetTime::default();
+        for row in rows {
+            time.add(row.hull as TypeID, row.seconds);
+        }
+        Ok(time)
+    }
+
+    /// Rebuilds all totals from the fleet_activity history
+    pub async fn rebuild(&self) -> Result<u64, sqlx::Error> {
+        let mut tx = self.db.begin().await?;
+
+        sqlx::query!("DELETE FROM fleet_time")
+            .execute(&mut tx)
+            .await?;
+
+        let result = sqlx::query!(
+            "INSERT INTO fleet_time (character_id, hull, seconds, boss_seconds)
+            SELECT character_id, hull, CAST(SUM(last_seen - first_seen) AS BIGINT),
+            CAST(SUM(CASE WHEN is_boss THEN last_seen - first_seen ELSE 0 END) AS BIGINT) FROM fleet_activity
+            GROUP BY character_id, hull"
+        )
+        .execute(&mut tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(result.rows_affected())
+    }
+}
@@ -0,0 +1,198 @@
+use serde::Serialize;
+
+use crate::config::WebhookConfig;
+
+/// Posts Discord compatible messages to an outgoing webhook. Each event has its own template in
+/// the config, events without a template are not sent. Templates use `{name}` placeholders.
+#[derive(Clone)]
+pub struct WebhookClient {
+    http: reqwest::Client,
+    config: WebhookConfig,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum WebhookError {
+    #[error("HTTP error while contacting the webhook")]
+    HTTPError(#[from] reqwest::Error),
+    #[error("webhook returned status {0}")]
+    Status(u16),
+}
+
+#[derive(Debug, Serialize)]
+struct WebhookPayload<'a> {
+    content: &'a str,
+}
+
+fn render(template: &str, vars: &[(&str, &str)]) -> String {
+    vars.iter().fold(template.to_string(), |message, (name, value)| {
+        message.replace(&format!("{{{}}}", name), value)
+    })
+}
+
+impl WebhookClient {
+    pub fn new(config: WebhookConfig) -> WebhookClient {
+        WebhookClient {
+            http: reqwest::Client::new(),
+            config,
+        }
+    }
+
+    /// Whether an event would be sent, so callers can skip building its variables
+    pub fn is_enabled(&self, event: &str) -> bool {
+        self.config.enable && self.config.templates.contains_key(event)
+    }
+
+    /// Sends an event in the background, failures are logged and never reach the caller
+    pub fn notify(&self, event: &str, vars: &[(&str, &str)]) {
+        if !self.config.enable {
+            return;
+        }
+
+        let message = match self.config.templates.get(event) {
+            Some(template) => render(template, vars),
+            None => return,
+        };
+
+        let client = self.clone();
+        let event = event.to_string();
+        tokio::spawn(async move {
+            if let Err(e) = client.deliver(&message).await {
+                warn!("Could not deliver webhook event {}: {:#?}", event, e);
+            }
+        });
+    }
+
+    async fn deliver(&self, message: &str) -> Result<(), WebhookError> {
+        let mut attempt = 0;
+        loop {
+            match self.send(message).await {
+                Ok(()) => return Ok(()),
+                Err(e) if attempt >= self.config.retries => return Err(e),
+                // Client errors other than rate limits won't go away by retrying
+                Err(WebhookError::Status(status)) if status != 429 && status < 500 => {
+                    return Err(WebhookError::Status(status))
+                }
+                Err(_) => (),
+            }
+
+            attempt += 1;
+            tokio::time::sleep(tokio::time::Duration::from_millis(
+                self.config.retry_delay_ms * attempt as u64,
+            ))
+            .await;
+        }
+    }
+
+    async fn send(&self, message: &str) -> Result<(), WebhookError> {
+        let response = self
+            .http
+            .post(&self.config.url)
+            .json(&WebhookPayload { content: message })
+            .send()
+            .await?;
+
+        match response.status().is_success() {
+            true => Ok(()),
+            false => Err(WebhookError::Status(response.status().as_u16())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+
+    /// A stand-in for the webhook, answers each request with the next status and hands back the bodies
+    fn stand_in(statuses: Vec<u16>) -> (String, mpsc::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/webhook", listener.local_addr().unwrap());
+        let (sender, receiver) = mpsc::channel();
+
+        std::thread::spawn(move || {
+            for status in statuses {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line == "\r\n" {
+                        break;
+                    }
+                    if let Some((name, value)) = line.split_once(':') {
+                        if name.eq_ignore_ascii_case("content-length") {
+                            content_length = value.trim().parse().unwrap();
+                        }
+                    }
+                }
+
+                let mut body = vec![0; content_length];
+                reader.read_exact(&mut body).unwrap();
+                sender.send(String::from_utf8(body).unwrap()).unwrap();
+
+                let mut stream = reader.into_inner();
+                write!(
+                    stream,
+                    "HTTP/1.1 {} Test\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    status
+                )
+                .unwrap();
+            }
+        });
+
+        (url, receiver)
+    }
+
+    fn config(url: String) -> WebhookConfig {
+        let mut templates = BTreeMap::new();
+        templates.insert("fleet_opened".to_string(), "{fc} opened a fleet".to_string());
+
+        WebhookConfig {
+            enable: true,
+            url,
+            retries: 2,
+            retry_delay_ms: 10,
+            templates,
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        assert_eq!(
+            render("{fc} handed {fleet} to {boss}", &[("fc", "A"), ("boss", "B")]),
+            "A handed {fleet} to B"
+        );
+    }
+
+    #[rocket::async_test]
+    async fn test_retries_until_delivered() {
+        let (url, bodies) = stand_in(vec![500, 429, 204]);
+        let client = WebhookClient::new(config(url));
+
+        let message = render(&client.config.templates["fleet_opened"], &[("fc", "Some FC")]);
+        client.deliver(&message).await.unwrap();
+
+        let bodies: Vec<String> = bodies.try_iter().collect();
+        assert_eq!(bodies.len(), 3);
+        for body in bodies {
+            assert_eq!(body, r#"{"content":"Some FC opened a fleet"}"#);
+        }
+    }
+
+    #[rocket::async_test]
+    async fn test_gives_up_on_client_errors() {
+        let (url, bodies) = stand_in(vec![404]);
+        let client = WebhookClient::new(config(url));
+
+        match client.deliver("hello").await {
+            Err(WebhookError::Status(404)) => (),
+            other => panic!("Unexpected result: {:?}", other),
+        }
+        assert_eq!(bodies.try_iter().count(), 1);
+    }
+}
@@ -42,3 +42,14 @@ pub async fn lookup(db: &crate::DB, ids: &[i64]) -> Result<HashMap<i64, Characte
         })
         .collect())
 }
+
+/// The name of a character, or its ID if we have never seen it
+pub async fn name_of(db: &crate::DB, id: i64) -> Result<String, sqlx::Error> {
+    Ok(
+        sqlx::query!("SELECT name FROM character WHERE id=$1", id)
+            .fetch_optional(db)
+            .await?
+            .map(|character| character.name)
+            .unwrap_or_else(|| id.to_string()),
+    )
+}
@@ -1,15 +1,20 @@
 use std::{cmp::min, collections::BTreeMap};
 
-use crate::data::variations::{Variation, Variator};
+use serde::Serialize;
 
-use eve_data_core::{Fitting, TypeID};
+use crate::data::variations::{Variation, Variator};
 
-#[derive(Debug)]
-struct SectionDiff {
-    missing: BTreeMap<TypeID, i64>,
-    extra: BTreeMap<TypeID, i64>,
-    upgraded: BTreeMap<TypeID, BTreeMap<TypeID, i64>>,
-    downgraded: BTreeMap<TypeID, BTreeMap<TypeID, i64>>,
+use eve_data_core::{Category, Fitting, TypeDB, TypeID};
+
+#[derive(Debug, Default, Serialize)]
+pub struct SectionDiff {
+    pub missing: BTreeMap<TypeID, i64>,
+    pub extra: BTreeMap<TypeID, i64>,
+    pub upgraded: BTreeMap<TypeID, BTreeMap<TypeID, i64>>,
+    pub downgraded: BTreeMap<TypeID, BTreeMap<TypeID, i64>>,
+    /// Missing items paired up with extra items in their place. They are still listed as missing
+    /// and extra as well.
+    pub swapped: BTreeMap<TypeID, BTreeMap<TypeID, i64>>,
 }
 
 #[derive(Debug)]
@@ -19,6 +24,80 @@ pub struct DiffResult {
     pub module_upgraded: BTreeMap<TypeID, BTreeMap<TypeID, i64>>,
     pub module_downgraded: BTreeMap<TypeID, BTreeMap<TypeID, i64>>,
     pub cargo_missing: BTreeMap<TypeID, i64>,
+
+    /// The module diff split up by slot (high/med/low/rig/drone, other for the rest)
+    pub slots: BTreeMap<&'static str, SectionDiff>,
+    /// Missing charges the fitted weapons can load, these are part of `cargo_missing` too
+    pub charge_missing: BTreeMap<TypeID, i64>,
+    /// Expected charges replaced with other charges for the same weapons
+    pub charge_swapped: BTreeMap<TypeID, BTreeMap<TypeID, i64>>,
+}
+
+fn slot_of(type_id: TypeID) -> &'static str {
+    match TypeDB::load_type(type_id) {
+        Ok(the_type) => the_type.slot().unwrap_or("other"),
+        Err(_) => "other",
+    }
+}
+
+fn by_slot(modules: &BTreeMap<TypeID, i64>) -> BTreeMap<&'static str, BTreeMap<TypeID, i64>> {
+    let mut slots = BTreeMap::new();
+    for (&type_id, &count) in modules {
+        slots
+            .entry(slot_of(type_id))
+            .or_insert_with(BTreeMap::new)
+            .insert(type_id, count);
+    }
+    slots
+}
+
+fn pair_swaps(
+    missing: &BTreeMap<TypeID, i64>,
+    extra: &BTreeMap<TypeID, i64>,
+) -> BTreeMap<TypeID, BTreeMap<TypeID, i64>> {
+    let mut extra = extra.clone();
+    let mut swapped = BTreeMap::new();
+    for (&from, &count) in missing {
+        let mut remaining = count;
+        for (&to, available) in extra.iter_mut() {
+            let sub = min(remaining, *available);
+            if sub > 0 {
+                remaining -= sub;
+                *available -= sub;
+                *swapped
+                    .entry(from)
+                    .or_insert_with(BTreeMap::new)
+                    .entry(to)
+                    .or_insert(0) += sub;
+            }
+        }
+    }
+    swapped
+}
+
+fn add(items: &mut BTreeMap<TypeID, i64>, type_id: TypeID, count: i64) {
+    *items.entry(type_id).or_insert(0) += count;
+}
+
+fn remove(items: &mut BTreeMap<TypeID, i64>, type_id: TypeID, count: i64) {
+    if let Some(have) = items.get_mut(&type_id) {
+        *have -= count;
+        if *have <= 0 {
+            items.remove(&type_id);
+        }
+    }
+}
+
+fn merge_nested(
+    into: &mut BTreeMap<TypeID, BTreeMap<TypeID, i64>>,
+    from: &BTreeMap<TypeID, BTreeMap<TypeID, i64>>,
+) {
+    for (&type_id, to) in from {
+        let entry = into.entry(type_id).or_insert_with(BTreeMap::new);
+        for (&to_id, &count) in to {
+            *entry.entry(to_id).or_insert(0) += count;
+        }
+    }
 }
 
 pub struct FitDiffer {}
@@ -99,12 +178,120 @@ impl FitDiffer {
             extra,
             upgraded,
             downgraded,
+            swapped: BTreeMap::new(),
+        }
+    }
+
+    fn module_diff(
+        expect: &BTreeMap<TypeID, i64>,
+        actual: &BTreeMap<TypeID, i64>,
+        variator: &'static Variator,
+    ) -> (SectionDiff, BTreeMap<&'static str, SectionDiff>) {
+        let expect = by_slot(expect);
+        let actual = by_slot(actual);
+        let empty = BTreeMap::new();
+
+        let mut merged = SectionDiff::default();
+        let mut slots = BTreeMap::new();
+        for &slot in expect.keys().chain(actual.keys()) {
+            if slots.contains_key(slot) {
+                continue;
+            }
+
+            let mut diff = Self::section_diff(
+                expect.get(slot).unwrap_or(&empty),
+                actual.get(slot).unwrap_or(&empty),
+                variator,
+            );
+            diff.swapped = pair_swaps(&diff.missing, &diff.extra);
+
+            merged.missing.extend(diff.missing.iter());
+            merged.extra.extend(diff.extra.iter());
+            merge_nested(&mut merged.upgraded, &diff.upgraded);
+            merge_nested(&mut merged.downgraded, &diff.downgraded);
+            merge_nested(&mut merged.swapped, &diff.swapped);
+            slots.insert(slot, diff);
+        }
+
+        (merged, slots)
+    }
+
+    fn charge_diff(
+        fit: &Fitting,
+        cargo: &SectionDiff,
+    ) -> (BTreeMap<TypeID, i64>, BTreeMap<TypeID, BTreeMap<TypeID, i64>>) {
+        let weapons: Vec<_> = fit
+            .modules
+            .keys()
+            .filter_map(|&id| TypeDB::load_type(id).ok())
+            .filter(|module| module.is_weapon())
+            .collect();
+        let loadable = |items: &BTreeMap<TypeID, i64>| -> BTreeMap<TypeID, i64> {
+            items
+                .iter()
+                .filter(|(&id, _)| match TypeDB::load_type(id) {
+                    Ok(charge) => {
+                        charge.category == Category::Charge
+                            && weapons.iter().any(|weapon| weapon.can_load(&charge))
+                    }
+                    Err(_) => false,
+                })
+                .map(|(&id, &count)| (id, count))
+                .collect()
+        };
+
+        let charge_missing = loadable(&cargo.missing);
+        let charge_swapped = pair_swaps(&charge_missing, &loadable(&cargo.extra));
+        (charge_missing, charge_swapped)
+    }
+
+    /// The lowest tier of `type_id` that is still acceptable in its place
+    fn lowest_acceptable(variator: &Variator, type_id: TypeID) -> TypeID {
+        variator
+            .get(type_id)
+            .and_then(|variations| {
+                variations
+                    .iter()
+                    .filter(|variation| variation.meta_diff >= 0)
+                    .min_by_key(|variation| (variation.meta_diff, variation.to != type_id))
+            })
+            .map(|variation| variation.to)
+            .unwrap_or(type_id)
+    }
+
+    /// Applies a diff to the fit it was made for. Upgrades are kept, extra modules are removed,
+    /// downgrades are replaced with the lowest acceptable tier and anything missing is added.
+    pub fn suggest(actual: &Fitting, diff: &DiffResult) -> Fitting {
+        let variator = crate::data::variations::get();
+        let mut modules = actual.modules.clone();
+        let mut cargo = actual.cargo.clone();
+
+        for (&type_id, &count) in &diff.module_extra {
+            remove(&mut modules, type_id, count);
+        }
+        for (&type_id, &count) in &diff.module_missing {
+            add(&mut modules, type_id, count);
+        }
+        for (&from, to) in &diff.module_downgraded {
+            for (&type_id, &count) in to {
+                remove(&mut modules, type_id, count);
+                add(&mut modules, Self::lowest_acceptable(variator, from), count);
+            }
+        }
+        for (&type_id, &count) in &diff.cargo_missing {
+            add(&mut cargo, type_id, count);
+        }
+
+        Fitting {
+            hull: actual.hull,
+            modules,
+            cargo,
         }
     }
 
     pub fn diff(expect: &Fitting, actual: &Fitting) -> DiffResult {
         let variator = crate::data::variations::get();
-        let modules = Self::section_diff(&expect.modules, &actual.modules, &variator);
+        let (modules, slots) = Self::module_diff(&expect.modules, &actual.modules, &variator);
         let cargo_changer = crate::data::variations::drug_handling().unwrap_or(BTreeMap::new());
         let mut mexcargo = expect.cargo.clone();
         mexcargo.retain(|id, _| !&variator.cargo_ignore.contains(id));
@@ -118,6 +305,7 @@ impl FitDiffer {
             }
         }
         let cargo = Self::section_diff(&mexcargo, &actual.cargo, &variator);
+        let (charge_missing, charge_swapped) = Self::charge_diff(actual, &cargo);
         // "Downgraded" cargo isn't a thing. Count those as missing
         let mut cargo_missing = cargo.missing;
         for (type_id, to) in cargo.downgraded {
@@ -145,6 +333,9 @@ impl FitDiffer {
             module_downgraded: modules.downgraded,
             module_upgraded: modules.upgraded,
             cargo_missing,
+            slots,
+            charge_missing,
+            charge_swapped,
         }
     }
 }
@@ -304,6 +495,19 @@ Sensor Booster II
             1
         );
 
+        let med = diff.slots.get("med").unwrap();
+        assert_eq!(med.missing.len(), 1);
+        assert_eq!(
+            *med.swapped
+                .get(&type_id!("Core X-Type 500MN Microwarpdrive"))
+                .unwrap()
+                .get(&type_id!("Sensor Booster II"))
+                .unwrap(),
+            1
+        );
+        assert!(diff.slots.get("low").unwrap().missing.is_empty());
+        assert_eq!(diff.slots.get("low").unwrap().downgraded.len(), 1);
+
         assert_eq!(diff.module_downgraded.len(), 1);
         assert_eq!(
             *diff
@@ -315,4 +519,70 @@ Sensor Booster II
             1
         );
     }
+
+    #[test]
+    fn test_suggest() {
+        let expect = Fitting::from_eft(
+            "[Atron, test]
+Mega Pulse Laser II
+Core X-Type 500MN Microwarpdrive
+Centum A-Type Multispectrum Energized Membrane
+Conflagration L x100
+",
+        )
+        .expect("Parsed?")
+        .pop()
+        .unwrap();
+        let actual = Fitting::from_eft(
+            "[Atron, test]
+Mega Pulse Laser II
+Centii A-Type Multispectrum Coating
+Sensor Booster II
+",
+        )
+        .expect("Parsed?")
+        .pop()
+        .unwrap();
+
+        let diff = FitDiffer::diff(&expect, &actual);
+        let suggested = FitDiffer::suggest(&actual, &diff);
+
+        assert!(FitDiffer::diff(&expect, &suggested)
+            .module_missing
+            .is_empty());
+        assert!(!suggested
+            .modules
+            .contains_key(&type_id!("Sensor Booster II")));
+        assert!(!suggested
+            .modules
+            .contains_key(&type_id!("Centii A-Type Multispectrum Coating")));
+        assert_eq!(
+            *suggested
+                .cargo
+                .get(&type_id!("Conflagration L"))
+                .unwrap(),
+            100
+        );
+    }
+
+    #[test]
+    fn test_diff_charges() {
+        let expect = Fitting::from_dna("17736:3057;4:12816;100::").unwrap();
+        let actual = Fitting::from_dna(&format!("17736:3057;4:{};100::", type_id!("Scorch L")))
+            .unwrap();
+
+        let diff = FitDiffer::diff(&expect, &actual);
+
+        assert!(diff.module_missing.is_empty());
+        assert_eq!(*diff.charge_missing.get(&12816).unwrap(), 100);
+        assert_eq!(
+            *diff
+                .charge_swapped
+                .get(&12816)
+                .unwrap()
+                .get(&type_id!("Scorch L"))
+                .unwrap(),
+            100
+        );
+    }
 }
@@ -4,6 +4,7 @@ pub mod fitdiffer;
 pub mod fits;
 pub mod fleets;
 pub mod implants;
+pub mod saved_fittings;
 pub mod skillplans;
 pub mod skills;
 pub mod tags;
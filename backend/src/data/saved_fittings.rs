@@ -0,0 +1,58 @@
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+use crate::core::esi::{ESIClient, ESIError, ESIScope};
+use eve_data_core::{FitError, Fitting, TypeDB, TypeID};
+
+#[derive(Debug, Deserialize)]
+struct ESIFittingItem {
+    type_id: TypeID,
+    flag: String,
+    quantity: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SavedFitting {
+    pub fitting_id: i64,
+    pub name: String,
+    ship_type_id: TypeID,
+    items: Vec<ESIFittingItem>,
+}
+
+impl SavedFitting {
+    pub fn to_fitting(&self) -> Result<Fitting, FitError> {
+        let mut modules = BTreeMap::new();
+        let mut cargo = BTreeMap::new();
+
+        for item in &self.items {
+            let is_cargo = match item.flag.as_str() {
+                "Cargo" => true,
+                "DroneBay" | "FighterBay" => false,
+                _ => TypeDB::load_type(item.type_id)?.is_always_cargo(),
+            };
+
+            let desto = match is_cargo {
+                true => &mut cargo,
+                false => &mut modules,
+            };
+            *desto.entry(item.type_id).or_insert(0) += item.quantity;
+        }
+
+        Ok(Fitting {
+            hull: self.ship_type_id,
+            modules,
+            cargo,
+        })
+    }
+}
+
+pub async fn get_saved_fittings(
+    esi_client: &ESIClient,
+    character_id: i64,
+) -> Result<Vec<SavedFitting>, ESIError> {
+    let path = format!("/v2/characters/{}/fittings/", character_id);
+    esi_client
+        .get(&path, character_id, ESIScope::Fittings_ReadFittings_v1)
+        .await
+}
@@ -114,3 +114,20 @@ pub async fn load_skills(
 
     Ok(Skills(result))
 }
+
+/// The tracked skills as last seen by `load_skills`, for jobs that run without an ESI request
+pub async fn load_known_skills(db: &crate::DB, character_id: i64) -> Result<Skills, sqlx::Error> {
+    let skills = sqlx::query!(
+        "SELECT skill_id, level FROM skill_current WHERE character_id = $1",
+        character_id
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(Skills(
+        skills
+            .into_iter()
+            .map(|skill| (skill.skill_id as TypeID, skill.level as SkillLevel))
+            .collect(),
+    ))
+}
@@ -1,6 +1,6 @@
 use std::collections::{BTreeMap, BTreeSet, HashMap};
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use crate::data::yamlhelper;
 
@@ -53,6 +53,8 @@ struct ModuleFile {
     from_attribute: Vec<FromAttributeEntry>,
     accept_t1: Vec<String>,
     cargo_ignore: Vec<String>,
+    #[serde(default)]
+    exclude: Vec<String>,
 }
 
 #[derive(Deserialize)]
@@ -85,8 +87,18 @@ struct Builder {
     file: ModuleFile,
 }
 
+/// A difference between the tiers in modules.yaml and the ones generated from the SDE
+#[derive(Debug, Serialize)]
+pub struct TierDifference {
+    pub from: TypeID,
+    pub to: TypeID,
+    pub curated: i64,
+    pub generated: Option<i64>,
+}
+
 impl Builder {
-    fn build() -> Result<Variator, TypeError> {
+    /// Only the tiers from modules.yaml
+    fn curated() -> Result<Builder, TypeError> {
         let mut builder = Builder {
             variations: BTreeMap::new(),
             cargo_ignore: BTreeSet::new(),
@@ -98,6 +110,13 @@ impl Builder {
         builder.add_by_attribute()?;
         builder.add_cargo_ignore()?;
 
+        Ok(builder)
+    }
+
+    fn build() -> Result<Variator, TypeError> {
+        let mut builder = Self::curated()?;
+        builder.add_generated()?;
+
         Ok(Variator {
             variations: builder.variations,
             cargo_ignore: builder.cargo_ignore,
@@ -149,6 +168,33 @@ impl Builder {
         }
         Ok(())
     }
+    fn add_generated(&mut self) -> Result<(), TypeError> {
+        let mut excluded = BTreeSet::new();
+        for entry in &self.file.exclude {
+            excluded.insert(TypeDB::id_of(entry)?);
+        }
+
+        let mut to_merge = vec![];
+        for family in TypeDB::variation_families()? {
+            if family.keys().any(|id| excluded.contains(id)) {
+                continue;
+            }
+
+            // Anything declared in modules.yaml overrides the generated tiers
+            let family: HashMap<TypeID, i64> = family
+                .into_iter()
+                .filter(|(id, _)| !self.variations.contains_key(id))
+                .collect();
+            if family.len() > 1 {
+                to_merge.push(family);
+            }
+        }
+        for merge in to_merge {
+            self.merge_tiers(merge);
+        }
+        Ok(())
+    }
+
     fn add_cargo_ignore(&mut self) -> Result<(), TypeError> {
         for entry in &self.file.cargo_ignore {
             self.cargo_ignore.insert(TypeDB::id_of(entry)?);
@@ -269,6 +315,43 @@ pub fn drug_handling() -> Result<BTreeMap<TypeID, DrugChanger>, TypeError> {
     Ok(drugmap)
 }
 
+/// Compares the tiers in modules.yaml with the generated ones, to find out which entries are still needed
+pub fn report() -> Result<Vec<TierDifference>, TypeError> {
+    let curated = Builder::curated()?;
+    let families = TypeDB::variation_families()?;
+
+    let mut family_of = HashMap::new();
+    for (i, family) in families.iter().enumerate() {
+        for &type_id in family.keys() {
+            family_of.insert(type_id, i);
+        }
+    }
+
+    let mut result = Vec::new();
+    for variations in curated.variations.values() {
+        for variation in variations {
+            if variation.from == variation.to {
+                continue;
+            }
+
+            let generated = family_of.get(&variation.from).and_then(|&i| {
+                let family = &families[i];
+                Some(family.get(&variation.to)? - family.get(&variation.from)?)
+            });
+            if generated.map(i64::signum) != Some(variation.meta_diff.signum()) {
+                result.push(TierDifference {
+                    from: variation.from,
+                    to: variation.to,
+                    curated: variation.meta_diff,
+                    generated,
+                });
+            }
+        }
+    }
+
+    Ok(result)
+}
+
 pub fn get() -> &'static Variator {
     &INSTANCE
 }
@@ -388,5 +471,13 @@ mod tests {
             "Multispectrum Energized Membrane II",
             Diff::Equal,
         );
+
+        // Generated from the SDE
+        test_diff("Co-Processor II", "Co-Processor I", Diff::Worse);
+        test_diff(
+            "Imperial Navy Drone Damage Amplifier",
+            "Drone Damage Amplifier II",
+            Diff::Worse,
+        );
     }
 }
@@ -69,6 +69,16 @@ tokio::runtime::Builder::new_multi_thread()
                 .await
                 .unwrap();
             let database = Arc::new(database);
+
+            // `rebuild-fleet-time` recalculates the cached time in fleet from fleet_activity and exits
+            if env::args().nth(1).as_deref() == Some("rebuild-fleet-time") {
+                let rows = core::time_in_fleet::TimeInFleetService::new(database.clone())
+                    .rebuild()
+                    .await
+                    .expect("Could not rebuild time in fleet");
+                println!("Rebuilt time in fleet ({} rows)", rows);
+                return;
+            }
         
             if config.fleet_updater.enable {
                 let fleet_updater =
@@ -81,6 +91,42 @@ tokio::runtime::Builder::new_multi_thread()
                     core::skill_updater::SkillUpdater::new(database.clone(), config.clone());
                 skill_updater.start();
             }
+
+            if config.affiliation_updater.enable {
+                let affiliation_updater =
+                    core::affiliation_updater::AffiliationUpdater::new(database.clone(), config.clone());
+                affiliation_updater.start();
+            }
+
+            if config.scope_checker.enable {
+                let scope_checker =
+                    core::scope_checker::ScopeChecker::new(database.clone(), config.clone());
+                scope_checker.start();
+            }
+
+            if config.milestone_notifier.enable {
+                let milestone_notifier =
+                    core::milestone_notifier::MilestoneNotifier::new(database.clone(), config.clone());
+                milestone_notifier.start();
+            }
+
+            if config.ban_notifier.enable {
+                let ban_notifier =
+                    core::ban_notifier::BanNotifier::new(database.clone(), config.clone());
+                ban_notifier.start();
+            }
+
+            if config.evasion_detector.enable {
+                let evasion_detector =
+                    core::evasion::EvasionDetector::new(database.clone(), config.clone());
+                evasion_detector.start();
+            }
+
+            if config.role_expiry.enable {
+                let role_expiry =
+                    core::role_expiry::RoleExpiry::new(database.clone(), config.clone());
+                role_expiry.start();
+            }
         
             let application = app::new(database, config);
             rocket::build()
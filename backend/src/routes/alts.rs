@@ -0,0 +1,242 @@
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use rocket::serde::json::Json;
+use serde::Serialize;
+
+use crate::{
+    app::Application,
+    core::{
+        auth::{authorize_character, AuthenticatedAccount},
+        esi::{split_scopes, ESIScope},
+    },
+    util::{madness::Madness, types::Character},
+};
+
+#[derive(Debug, Serialize)]
+struct LinkedCharacter {
+    character: Character,
+    is_main: bool,
+    has_token: bool,
+    scopes: Vec<String>,
+    missing_scopes: Vec<&'static str>,
+    other_accounts: Vec<Character>,
+}
+
+#[derive(Debug, Serialize)]
+struct AltsResponse {
+    characters: Vec<LinkedCharacter>,
+}
+
+#[derive(Debug, Serialize)]
+struct AltLink {
+    account_id: i64,
+    alt_id: i64,
+}
+
+#[derive(Debug, Serialize)]
+struct AltGraphResponse {
+    characters: Vec<Character>,
+    links: Vec<AltLink>,
+}
+
+#[derive(Debug, Serialize)]
+struct SharedAlt {
+    character: Character,
+    accounts: Vec<Character>,
+}
+
+async fn linked_accounts(
+    app: &Application,
+    alt_id: i64,
+    except: i64,
+) -> Result<Vec<Character>, Madness> {
+    Ok(sqlx::query!(
+        "SELECT id, name FROM alt_character JOIN character ON character.id=alt_character.account_id
+        WHERE alt_id=$1 AND account_id!=$2 ORDER BY name ASC",
+        alt_id,
+        except
+    )
+    .fetch_all(app.get_db())
+    .await?
+    .into_iter()
+    .map(|account| Character {
+        id: account.id,
+        name: account.name,
+        corporation_id: None,
+    })
+    .collect())
+}
+
+#[get("/api/alts")]
+async fn list_alts(
+    account: AuthenticatedAccount,
+    app: &rocket::State<Application>,
+) -> Result<Json<AltsResponse>, Madness> {
+    let rows = sqlx::query!(
+        "SELECT character.id, character.name, character.corporation_id, refresh_token.scopes AS \"scopes?\"
+        FROM character LEFT JOIN refresh_token ON refresh_token.character_id=character.id
+        WHERE character.id=$1 OR character.id IN (SELECT alt_id FROM alt_character WHERE account_id=$1)
+        ORDER BY character.name ASC",
+        account.id
+    )
+    .fetch_all(app.get_db())
+    .await?;
+
+    let mut characters = Vec::new();
+    for row in rows {
+        let scopes = row
+            .scopes
+            .as_deref()
+            .map(split_scopes)
+            .unwrap_or_default();
+        let missing_scopes = ESIScope::pilot_scopes()
+            .into_iter()
+            .map(|scope| scope.as_str())
+            .filter(|scope| !scopes.contains(*scope))
+            .collect();
+
+        characters.push(LinkedCharacter {
+            is_main: row.id == account.id,
+            has_token: row.scopes.is_some(),
+            scopes: scopes.into_iter().collect(),
+            missing_scopes,
+            other_accounts: linked_accounts(app, row.id, account.id).await?,
+            character: Character {
+                id: row.id,
+                name: row.name,
+                corporation_id: row.corporation_id,
+            },
+        });
+    }
+
+    Ok(Json(AltsResponse { characters }))
+}
+
+#[delete("/api/alts/<alt_id>")]
+async fn unlink_alt(
+    account: AuthenticatedAccount,
+    alt_id: i64,
+    app: &rocket::State<Application>,
+) -> Result<&'static str, Madness> {
+    account.require_session()?;
+
+    let result = sqlx::query!(
+        "DELETE FROM alt_character WHERE account_id=$1 AND alt_id=$2",
+        account.id,
+        alt_id
+    )
+    .execute(app.get_db())
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(Madness::NotFound("Alt is not linked to this account"));
+    }
+
+    Ok("OK")
+}
+
+#[get("/api/alts/graph?<character_id>")]
+async fn alt_graph(
+    account: AuthenticatedAccount,
+    character_id: i64,
+    app: &rocket::State<Application>,
+) -> Result<Json<AltGraphResponse>, Madness> {
+    authorize_character(app.get_db(), &account, character_id, Some("pilot-view")).await?;
+
+    // Walk the links in both directions, accounts can share alts
+    let mut seen = BTreeSet::new();
+    let mut links = Vec::new();
+    let mut queue = VecDeque::new();
+    seen.insert(character_id);
+    queue.push_back(character_id);
+
+    while let Some(id) = queue.pop_front() {
+        for link in sqlx::query!(
+            "SELECT account_id, alt_id FROM alt_character WHERE account_id=$1 OR alt_id=$1",
+            id
+        )
+        .fetch_all(app.get_db())
+        .await?
+        {
+            for next in [link.account_id, link.alt_id] {
+                if seen.insert(next) {
+                    queue.push_back(next);
+                }
+            }
+            if link.account_id == id {
+                links.push(AltLink {
+                    account_id: link.account_id,
+                    alt_id: link.alt_id,
+                });
+            }
+        }
+    }
+
+    let ids: Vec<i64> = seen.into_iter().collect();
+    let characters = sqlx::query!(
+        "SELECT id, name, corporation_id FROM character WHERE id = ANY($1) ORDER BY name ASC",
+        &ids
+    )
+    .fetch_all(app.get_db())
+    .await?
+    .into_iter()
+    .map(|character| Character {
+        id: character.id,
+        name: character.name,
+        corporation_id: character.corporation_id,
+    })
+    .collect();
+
+    Ok(Json(AltGraphResponse { characters, links }))
+}
+
+#[get("/api/alts/shared")]
+async fn shared_alts(
+    account: AuthenticatedAccount,
+    app: &rocket::State<Application>,
+) -> Result<Json<Vec<SharedAlt>>, Madness> {
+    account.require_access("pilot-view")?;
+
+    let rows = sqlx::query!(
+        "SELECT alt.id AS alt_id, alt.name AS alt_name, main.id AS account_id, main.name AS account_name
+        FROM alt_character
+        JOIN character AS alt ON alt.id=alt_character.alt_id
+        JOIN character AS main ON main.id=alt_character.account_id
+        WHERE alt_id IN (SELECT alt_id FROM alt_character GROUP BY alt_id HAVING COUNT(*) > 1)
+        ORDER BY alt.name ASC, main.name ASC"
+    )
+    .fetch_all(app.get_db())
+    .await?;
+
+    let mut shared: BTreeMap<i64, SharedAlt> = BTreeMap::new();
+    for row in rows {
+        let (alt_id, alt_name) = (row.alt_id, row.alt_name);
+        shared
+            .entry(alt_id)
+            .or_insert_with(|| SharedAlt {
+                character: Character {
+                    id: alt_id,
+                    name: alt_name,
+                    corporation_id: None,
+                },
+                accounts: Vec::new(),
+            })
+            .accounts
+            .push(Character {
+                id: row.account_id,
+                name: row.account_name,
+                corporation_id: None,
+            });
+    }
+
+    Ok(Json(shared.into_values().collect()))
+}
+
+pub fn routes() -> Vec<rocket::Route> {
+    routes![
+        list_alts,   //  GET     /api/alts
+        unlink_alt,  //  DELETE  /api/alts/<alt_id>
+        alt_graph,   //  GET     /api/alts/graph?<character_id>
+        shared_alts  //  GET     /api/alts/shared
+    ]
+}
@@ -1,11 +1,16 @@
 use crate::{
     app::Application,
-    core::{auth::AuthenticatedAccount, sse::Event},
+    core::{
+        audit::{self, AuditEntry},
+        auth::AuthenticatedAccount,
+        sse::Event,
+    },
     util::{madness::Madness, types::Character},
 };
 
 use rocket::serde::json::Json;
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 
 #[derive(Deserialize, Serialize)]
 struct Announcement {
@@ -108,16 +113,28 @@ async fn create(
 
     let now = chrono::Utc::now().timestamp();
 
-    sqlx::query!(
-        "INSERT INTO announcement (message, is_alert, pages, created_by_id, created_at) VALUES ($1, $2, $3, $4, $5)",
+    let mut tx = app.get_db().begin().await?;
+    let announcement = sqlx::query!(
+        "INSERT INTO announcement (message, is_alert, pages, created_by_id, created_at) VALUES ($1, $2, $3, $4, $5) RETURNING id",
         body.message,
         body.is_alert,
         body.pages,
         account.id,
         now
     )
-    .execute(app.get_db())
+    .fetch_one(&mut tx)
+    .await?;
+
+    audit::record(
+        &mut tx,
+        AuditEntry::new(account.id, "announcement-create", "announcement", Some(announcement.id))
+            .after(json!({ "message": body.message, "is_alert": body.is_alert, "pages": body.pages })),
+    )
     .await?;
+    tx.commit().await?;
+
+    app.webhook_client
+        .notify("announcement_created", &[("message", body.message.as_str())]);
 
     // Send an updated array of announcements to users active on the site
     let payloads = get_active_announcements(app).await?;
@@ -168,6 +185,7 @@ async fn update(
         )));
     }
 
+    let mut tx = app.get_db().begin().await?;
     sqlx::query!(
         "UPDATE announcement SET message=$1, is_alert=$2, pages=$3, created_by_id=$4 WHERE id=$5",
         body.message,
@@ -176,9 +194,18 @@ async fn update(
         account.id,
         announcement_id
     )
-    .execute(app.get_db())
+    .execute(&mut tx)
     .await?;
 
+    audit::record(
+        &mut tx,
+        AuditEntry::new(account.id, "announcement-update", "announcement", Some(announcement_id))
+            .before(json!(announcement))
+            .after(json!({ "message": body.message, "is_alert": body.is_alert, "pages": body.pages })),
+    )
+    .await?;
+    tx.commit().await?;
+
     // Send an updated array of announcements to users active on the site
     let payloads = get_active_announcements(app).await?;
 
@@ -229,14 +256,23 @@ async fn revoke(
 
     let now = chrono::Utc::now().timestamp();
 
+    let mut tx = app.get_db().begin().await?;
     sqlx::query!(
         "UPDATE announcement SET revoked_by_id=$1, revoked_at=$2 WHERE id=$3",
         account.id,
         now,
         announcement_id
     )
-    .execute(app.get_db())
+    .execute(&mut tx)
+    .await?;
+
+    audit::record(
+        &mut tx,
+        AuditEntry::new(account.id, "announcement-revoke", "announcement", Some(announcement_id))
+            .before(json!(announcement)),
+    )
     .await?;
+    tx.commit().await?;
 
     // Send an updated array of announcements to users active on the site
     let payloads = get_active_announcements(app).await?;
@@ -0,0 +1,126 @@
+use rocket::{http::ContentType, serde::json::Json};
+use serde::Serialize;
+
+use crate::{
+    app::Application,
+    core::auth::AuthenticatedAccount,
+    util::{madness::Madness, types::Character},
+};
+
+#[derive(Debug, FromForm)]
+struct AuditFilter {
+    actor_id: Option<i64>,
+    action: Option<String>,
+    target_type: Option<String>,
+    target_id: Option<i64>,
+    since: Option<i64>,
+    until: Option<i64>,
+    limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+struct AuditLogEntry {
+    id: i64,
+    actor: Character,
+    action: String,
+    target_type: String,
+    target_id: Option<i64>,
+    before: Option<String>,
+    after: Option<String>,
+    logged_at: i64,
+}
+
+async fn query_log(app: &Application, filter: &AuditFilter) -> Result<Vec<AuditLogEntry>, Madness> {
+    let limit = filter.limit.unwrap_or(500).min(5000);
+
+    Ok(sqlx::query!(
+        "SELECT audit_log.id, actor_id, character.name AS actor_name, action, target_type, target_id, before, after, logged_at
+        FROM audit_log JOIN character ON character.id=audit_log.actor_id
+        WHERE ($1::BIGINT IS NULL OR actor_id=$1)
+        AND ($2::TEXT IS NULL OR action=$2)
+        AND ($3::TEXT IS NULL OR target_type=$3)
+        AND ($4::BIGINT IS NULL OR target_id=$4)
+        AND ($5::BIGINT IS NULL OR logged_at >= $5)
+        AND ($6::BIGINT IS NULL OR logged_at <= $6)
+        ORDER BY logged_at DESC, audit_log.id DESC LIMIT $7",
+        filter.actor_id,
+        filter.action,
+        filter.target_type,
+        filter.target_id,
+        filter.since,
+        filter.until,
+        limit
+    )
+    .fetch_all(app.get_db())
+    .await?
+    .into_iter()
+    .map(|entry| AuditLogEntry {
+        id: entry.id,
+        actor: Character {
+            id: entry.actor_id,
+            name: entry.actor_name,
+            corporation_id: None,
+        },
+        action: entry.action,
+        target_type: entry.target_type,
+        target_id: entry.target_id,
+        before: entry.before,
+        after: entry.after,
+        logged_at: entry.logged_at,
+    })
+    .collect())
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[get("/api/audit?<filter..>")]
+async fn list(
+    account: AuthenticatedAccount,
+    app: &rocket::State<Application>,
+    filter: AuditFilter,
+) -> Result<Json<Vec<AuditLogEntry>>, Madness> {
+    account.require_access("audit-view")?;
+
+    Ok(Json(query_log(app, &filter).await?))
+}
+
+#[get("/api/audit/export?<filter..>")]
+async fn export(
+    account: AuthenticatedAccount,
+    app: &rocket::State<Application>,
+    filter: AuditFilter,
+) -> Result<(ContentType, String), Madness> {
+    account.require_access("audit-view")?;
+
+    let mut csv = String::from("id,logged_at,actor_id,actor_name,action,target_type,target_id,before,after\n");
+    for entry in query_log(app, &filter).await? {
+        let row = [
+            entry.id.to_string(),
+            entry.logged_at.to_string(),
+            entry.actor.id.to_string(),
+            csv_field(&entry.actor.name),
+            csv_field(&entry.action),
+            csv_field(&entry.target_type),
+            entry.target_id.map(|id| id.to_string()).unwrap_or_default(),
+            csv_field(entry.before.as_deref().unwrap_or("")),
+            csv_field(entry.after.as_deref().unwrap_or("")),
+        ];
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+
+    Ok((ContentType::CSV, csv))
+}
+
+pub fn routes() -> Vec<rocket::Route> {
+    routes![
+        list,   //  GET     /api/audit
+        export  //  GET     /api/audit/export
+    ]
+}
@@ -4,8 +4,12 @@ use serde::{Deserialize, Serialize};
 use zxcvbn::{zxcvbn, ZxcvbnError};
 
 use crate::app;
-use crate::core::auth::{AuthenticatedAccount, AuthenticationError, CookieSetter};
+use crate::core::auth::{
+    create_session, AuthenticatedAccount, AuthenticationError, CookieSetter, UserAgent,
+};
+use crate::core::ban::create_appeal_token;
 use crate::core::esi::ESIScope;
+use crate::core::scope_checker::{account_health, ScopeHealth};
 use crate::util::{madness::Madness, types};
 
 #[derive(Deserialize)]
@@ -88,6 +92,7 @@ struct WhoamiResponse {
     account_id: i64,
     access: Vec<&'static str>,
     characters: Vec<types::Character>,
+    revoked_tokens: Vec<i64>,
 }
 
 #[get("/api/auth/whoami")]
@@ -120,17 +125,37 @@ async fn whoami(
     }
 
     let mut access_levels = Vec::new();
-    for key in account.access {
+    for key in &account.access {
         access_levels.push(key.as_str());
     }
 
+    // Characters that need to log in again before we can use ESI for them
+    let revoked_tokens = sqlx::query!(
+        "SELECT character_id FROM revoked_token WHERE character_id=$1 OR character_id IN (SELECT alt_id FROM alt_character WHERE account_id=$1)",
+        account.id
+    )
+    .fetch_all(app.get_db())
+    .await?
+    .into_iter()
+    .map(|r| r.character_id)
+    .collect();
+
     Ok(Json(WhoamiResponse {
         account_id: account.id,
         access: access_levels,
         characters,
+        revoked_tokens,
     }))
 }
 
+#[get("/api/auth/scopes")]
+async fn scope_health(
+    app: &rocket::State<app::Application>,
+    account: AuthenticatedAccount,
+) -> Result<Json<Vec<ScopeHealth>>, Madness> {
+    Ok(Json(account_health(app.get_db(), account.id).await?))
+}
+
 #[get("/api/auth/logout")]
 async fn logout<'r>(
     app: &rocket::State<app::Application>,
@@ -144,6 +169,12 @@ async fn logout<'r>(
         )
         .execute(app.get_db())
         .await?;
+
+        if let Some(session_id) = account.session_id {
+            sqlx::query!("DELETE FROM session WHERE id=$1", session_id)
+                .execute(app.get_db())
+                .await?;
+        }
     }
 
     Ok(CookieSetter(
@@ -159,18 +190,9 @@ fn login_url(alt: bool, fc: bool, app: &rocket::State<app::Application>) -> Stri
         false => "normal",
     };
 
-    let mut scopes = vec![
-        ESIScope::PublicData,
-        ESIScope::Skills_ReadSkills_v1,
-        ESIScope::Clones_ReadImplants_v1,
-    ];
+    let mut scopes = ESIScope::pilot_scopes();
     if fc {
-        scopes.extend(vec![
-            ESIScope::Fleets_ReadFleet_v1,
-            ESIScope::Fleets_WriteFleet_v1,
-            ESIScope::UI_OpenWindow_v1,
-            ESIScope::Search_v1,
-        ])
+        scopes.extend(ESIScope::fc_scopes())
     }
 
     format!(
@@ -193,6 +215,7 @@ struct PublicBanPayload {
     category: String,
     expires_at: Option<i64>,
     reason: Option<String>,
+    appeal_token: Option<String>,
 }
 
 #[post("/api/auth/cb", data = "<input>")]
@@ -200,6 +223,7 @@ async fn callback(
     input: Json<CallbackData<'_>>,
     app: &rocket::State<app::Application>,
     account_raw: Result<AuthenticatedAccount, AuthenticationError>,
+    user_agent: UserAgent,
 ) -> Result<CookieSetter, Madness> {
     let account = match account_raw {
         Err(AuthenticationError::MissingCookie) => None,
@@ -225,6 +249,9 @@ async fn callback(
             category: ban.entity.to_owned().unwrap().category,
             expires_at: ban.revoked_at,
             reason: ban.public_reason.to_owned(),
+            appeal_token: ban
+                .id
+                .map(|ban_id| create_appeal_token(&app.token_secret, ban_id, character_id)),
         };
 
         if let Ok(json) = serde_json::to_string(&payload) {
@@ -261,14 +288,21 @@ async fn callback(
                 .execute(app.get_db())
                 .await?;
             }
+
+            // Adding an alt replaces the current session rather than starting a second one
+            if let Some(session_id) = account.session_id {
+                sqlx::query!("DELETE FROM session WHERE id=$1", session_id)
+                    .execute(app.get_db())
+                    .await?;
+            }
             account.id
         } else {
             character_id
         };
 
-    Ok(crate::core::auth::create_cookie(app, logged_in_account))
+    Ok(create_session(app, logged_in_account, user_agent.0.as_deref()).await?)
 }
 
 pub fn routes() -> Vec<rocket::Route> {
-    routes![whoami, logout, login_url, callback, set_wiki_passwd]
+    routes![whoami, logout, login_url, callback, set_wiki_passwd, scope_health]
 }
@@ -1,7 +1,15 @@
-use crate::{app::Application, core::auth::AuthenticatedAccount, util::madness::Madness};
+use crate::{
+    app::Application,
+    core::{
+        audit::{self, AuditEntry},
+        auth::AuthenticatedAccount,
+    },
+    util::madness::Madness,
+};
 
 use rocket::serde::json::Json;
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 
 #[derive(Debug, Serialize)]
 struct Badge {
@@ -222,6 +230,7 @@ async fn assign_badge(
     }
 
     let now = chrono::Utc::now().timestamp();
+    let mut tx = app.get_db().begin().await?;
     sqlx::query!(
         "INSERT INTO badge_assignment (characterId, badgeId, grantedById, grantedAt) VALUES ($1, $2, $3, $4)",
         character.id,
@@ -229,9 +238,17 @@ async fn assign_badge(
         account.id,
         now
     )
-    .execute(app.get_db())
+    .execute(&mut tx)
     .await?;
 
+    audit::record(
+        &mut tx,
+        AuditEntry::new(account.id, "badge-assign", "character", Some(character.id))
+            .after(json!({ "badge_id": badge_id, "badge": badge.name })),
+    )
+    .await?;
+    tx.commit().await?;
+
     Ok("Ok")
 }
 
@@ -245,14 +262,25 @@ async fn revoke_badge(
 ) -> Result<&'static str, Madness> {
     account.require_access("badges-manage")?;
 
-    sqlx::query!(
+    let mut tx = app.get_db().begin().await?;
+    let result = sqlx::query!(
         "DELETE FROM badge_assignment WHERE characterId=$1 AND badgeId=$2",
         character_id,
         badge_id
     )
-    .execute(app.get_db())
+    .execute(&mut tx)
     .await?;
 
+    if result.rows_affected() > 0 {
+        audit::record(
+            &mut tx,
+            AuditEntry::new(account.id, "badge-revoke", "character", Some(character_id))
+                .before(json!({ "badge_id": badge_id })),
+        )
+        .await?;
+    }
+    tx.commit().await?;
+
     Ok("Ok")
 }
 
@@ -1,6 +1,12 @@
 use crate::{
     app::Application,
-    core::auth::AuthenticatedAccount,
+    core::{
+        audit::{self, AuditEntry},
+        auth::AuthenticatedAccount,
+        ban::decode_appeal_token,
+        sse::Event,
+    },
+    data::character,
     util::{
         madness::Madness,
         types::{Ban, Character, Entity},
@@ -8,7 +14,8 @@ use crate::{
 };
 
 use rocket::serde::json::Json;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
 use sqlx::types::chrono::Utc;
 
 #[derive(Deserialize)]
@@ -91,20 +98,32 @@ async fn create(
     }
 
     let e = req_body.entity.as_ref().unwrap();
+
+    // Account bans are issued against the main character
+    let esi_category = match e.category.as_str() {
+        "Account" => "character".to_string(),
+        category => category.to_lowercase(),
+    };
     let esi_res: EsiResponse = app
         .esi_client
-        .get_unauthenticated(&format!(
-            "/latest/{}s/{}",
-            req_body.entity.as_ref().unwrap().category.to_lowercase(),
-            req_body.entity.as_ref().unwrap().id
-        ))
+        .get_unauthenticated(&format!("/latest/{}s/{}", esi_category, e.id))
         .await?;
 
-    // Stop FCs from banning other FCs
+    // Stop FCs from banning other FCs, account bans also cover every alt linked to the main
     // See: https://github.com/Contingency-Incursions/legacy-waitlist/issues/43
+    let mut banned_characters = vec![e.id];
+    if e.category == "Account" {
+        for alt in sqlx::query!("SELECT alt_id FROM alt_character WHERE account_id=$1", e.id)
+            .fetch_all(app.get_db())
+            .await?
+        {
+            banned_characters.push(alt.alt_id);
+        }
+    }
     if let Some(admin) = sqlx::query!(
-        "SELECT * FROM admin WHERE character_id=$1",
-        e.id
+        "SELECT role FROM admin WHERE character_id = ANY($1)
+        AND (expires_at IS NULL OR expires_at > EXTRACT(EPOCH FROM NOW())) LIMIT 1",
+        &banned_characters
     )
     .fetch_optional(app.get_db())
     .await? {
@@ -122,8 +141,9 @@ async fn create(
         }
     };
 
-    sqlx::query!(
-        "INSERT INTO ban (entity_type, entity_id, entity_name, issued_at, issued_by, reason, public_reason, revoked_at) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+    let mut tx = app.get_db().begin().await?;
+    let ban = sqlx::query!(
+        "INSERT INTO ban (entity_type, entity_id, entity_name, issued_at, issued_by, reason, public_reason, revoked_at) VALUES ($1, $2, $3, $4, $5, $6, $7, $8) RETURNING id",
         e.category,
         e.id,
         esi_res.name,
@@ -133,8 +153,35 @@ async fn create(
         req_body.public_reason,
         expires_at,
     )
-    .execute(app.get_db())
+    .fetch_one(&mut tx)
+    .await?;
+
+    audit::record(
+        &mut tx,
+        AuditEntry::new(account.id, "ban-create", "ban", Some(ban.id)).after(json!({
+            "entity_type": e.category,
+            "entity_id": e.id,
+            "entity_name": esi_res.name,
+            "reason": req_body.reason,
+            "public_reason": req_body.public_reason,
+            "revoked_at": expires_at,
+        })),
+    )
     .await?;
+    tx.commit().await?;
+
+    if app.webhook_client.is_enabled("ban_issued") {
+        let fc = character::name_of(app.get_db(), account.id).await?;
+        app.webhook_client.notify(
+            "ban_issued",
+            &[
+                ("fc", fc.as_str()),
+                ("entity_type", e.category.as_str()),
+                ("entity_name", esi_res.name.as_str()),
+                ("public_reason", req_body.public_reason.as_deref().unwrap_or("")),
+            ],
+        );
+    }
 
     Ok("Ok")
 }
@@ -165,7 +212,7 @@ async fn update(
 
     let now = Utc::now().timestamp();
 
-    if let None = sqlx::query!(
+    let previous = match sqlx::query!(
         "SELECT * FROM ban WHERE id=$1 AND (revoked_at IS NULL OR revoked_at > $2)",
         ban_id,
         now
@@ -173,10 +220,13 @@ async fn update(
     .fetch_optional(app.get_db())
     .await?
     {
-        return Err(Madness::BadRequest(format!(
-            "Cannot revoke invalid ban. It is either invalid or doesn't exist"
-        )));
-    }
+        Some(ban) => ban,
+        None => {
+            return Err(Madness::BadRequest(format!(
+                "Cannot revoke invalid ban. It is either invalid or doesn't exist"
+            )))
+        }
+    };
 
     let expires_at = match req_body.revoked_at.as_ref() {
         None => None,
@@ -186,6 +236,7 @@ async fn update(
         }
     };
 
+    let mut tx = app.get_db().begin().await?;
     sqlx::query!(
         "UPDATE
             ban
@@ -204,9 +255,30 @@ async fn update(
         now,
         ban_id
     )
-    .execute(app.get_db())
+    .execute(&mut tx)
     .await?;
 
+    audit::record(
+        &mut tx,
+        AuditEntry::new(account.id, "ban-update", "ban", Some(ban_id))
+            .before(json!({
+                "reason": previous.reason,
+                "public_reason": previous.public_reason,
+                "revoked_at": previous.revoked_at,
+                "issued_by": previous.issued_by,
+                "issued_at": previous.issued_at,
+            }))
+            .after(json!({
+                "reason": req_body.reason,
+                "public_reason": req_body.public_reason,
+                "revoked_at": expires_at,
+                "issued_by": account.id,
+                "issued_at": now,
+            })),
+    )
+    .await?;
+    tx.commit().await?;
+
     Ok("Ok")
 }
 
@@ -241,15 +313,25 @@ async fn revoke(
             )));
         }
 
+        let mut tx = app.get_db().begin().await?;
         sqlx::query!(
             "UPDATE ban SET revoked_at=$1, revoked_by=$2 WHERE id=$3",
             now,
             account.id,
             ban_id
         )
-        .execute(app.get_db())
+        .execute(&mut tx)
         .await?;
 
+        audit::record(
+            &mut tx,
+            AuditEntry::new(account.id, "ban-revoke", "ban", Some(ban_id))
+                .before(json!({ "revoked_at": ban.revoked_at, "revoked_by": ban.revoked_by }))
+                .after(json!({ "revoked_at": now, "revoked_by": account.id })),
+        )
+        .await?;
+        tx.commit().await?;
+
         return Ok("Ok");
     }
 
@@ -259,12 +341,379 @@ async fn revoke(
     )));
 }
 
+#[get("/api/v2/bans/history/<entity_type>/<entity_id>")]
+async fn entity_history(
+    account: AuthenticatedAccount,
+    app: &rocket::State<Application>,
+    entity_type: &str,
+    entity_id: i64,
+) -> Result<Json<Vec<Ban>>, Madness> {
+    account.require_access("bans-manage")?;
+
+    if !["Account", "Character", "Corporation", "Alliance"].contains(&entity_type) {
+        return Err(Madness::BadRequest(format!(
+            "Unknown entity type: {}",
+            entity_type
+        )));
+    }
+
+    if let Some(bans) = app.ban_service.all_bans(entity_id, entity_type).await? {
+        return Ok(Json(bans));
+    }
+
+    Ok(Json(Vec::new()))
+}
+
+#[derive(Debug, Serialize)]
+struct AccountBanAlts {
+    ban: Ban,
+    alts: Vec<Character>,
+}
+
+#[get("/api/v2/bans/accounts")]
+async fn account_ban_alts(
+    account: AuthenticatedAccount,
+    app: &rocket::State<Application>,
+) -> Result<Json<Vec<AccountBanAlts>>, Madness> {
+    account.require_access("bans-manage")?;
+
+    let now = Utc::now().timestamp();
+    let accounts = sqlx::query!(
+        "SELECT DISTINCT entity_id FROM ban WHERE entity_type='Account' AND (revoked_at IS NULL OR revoked_at > $1)",
+        now
+    )
+    .fetch_all(app.get_db())
+    .await?;
+
+    let mut report = Vec::new();
+    for banned in accounts {
+        let alts: Vec<Character> = sqlx::query!(
+            "SELECT id, name, corporation_id FROM alt_character JOIN character ON character.id=alt_character.alt_id
+            WHERE account_id=$1 ORDER BY name ASC",
+            banned.entity_id
+        )
+        .fetch_all(app.get_db())
+        .await?
+        .into_iter()
+        .map(|alt| Character {
+            id: alt.id,
+            name: alt.name,
+            corporation_id: alt.corporation_id,
+        })
+        .collect();
+
+        if let Some(bans) = app.ban_service.active_bans(banned.entity_id, "Account").await? {
+            for ban in bans {
+                report.push(AccountBanAlts {
+                    ban,
+                    alts: alts.clone(),
+                });
+            }
+        }
+    }
+
+    Ok(Json(report))
+}
+
+#[derive(Debug, Serialize)]
+struct EvasionFlag {
+    reason: String,
+    details: String,
+    flagged_at: i64,
+}
+
+#[get("/api/v2/bans/evasion/<character_id>")]
+async fn evasion_flags(
+    account: AuthenticatedAccount,
+    app: &rocket::State<Application>,
+    character_id: i64,
+) -> Result<Json<Vec<EvasionFlag>>, Madness> {
+    account.require_access("bans-manage")?;
+
+    let flags = sqlx::query_as!(
+        EvasionFlag,
+        "SELECT reason, details, flagged_at FROM evasion_flag WHERE character_id=$1 ORDER BY flagged_at DESC",
+        character_id
+    )
+    .fetch_all(app.get_db())
+    .await?;
+
+    Ok(Json(flags))
+}
+
+#[derive(Deserialize)]
+struct AppealRequest {
+    token: String,
+    appeal: String,
+}
+
+#[derive(Debug, Serialize)]
+struct AppealNotification {
+    ban_id: i64,
+    character: Character,
+}
+
+#[post("/api/v2/bans/appeals", data = "<req_body>")]
+async fn submit_appeal(
+    app: &rocket::State<Application>,
+    req_body: Json<AppealRequest>,
+) -> Result<&'static str, Madness> {
+    // The pilot is banned and can't log in, the token from the login response identifies them instead
+    let (ban_id, character_id) = match decode_appeal_token(&app.token_secret, &req_body.token) {
+        Some(t) => t,
+        None => return Err(Madness::BadRequest("Invalid appeal token".to_string())),
+    };
+
+    if req_body.appeal.len() < 20 || req_body.appeal.len() > 5000 {
+        return Err(Madness::BadRequest("Invalid appeal".to_string()));
+    }
+
+    let now = Utc::now().timestamp();
+    if sqlx::query!(
+        "SELECT id FROM ban WHERE id=$1 AND (revoked_at IS NULL OR revoked_at > $2)",
+        ban_id,
+        now
+    )
+    .fetch_optional(app.get_db())
+    .await?
+    .is_none()
+    {
+        return Err(Madness::BadRequest("This ban is no longer active".to_string()));
+    }
+
+    if sqlx::query!(
+        "SELECT id FROM ban_appeal WHERE ban_id=$1 AND character_id=$2 AND state='pending'",
+        ban_id,
+        character_id
+    )
+    .fetch_optional(app.get_db())
+    .await?
+    .is_some()
+    {
+        return Err(Madness::BadRequest(
+            "You already have an appeal waiting for review".to_string(),
+        ));
+    }
+
+    sqlx::query!(
+        "INSERT INTO ban_appeal (ban_id, character_id, appeal, submitted_at) VALUES ($1, $2, $3, $4)",
+        ban_id,
+        character_id,
+        req_body.appeal,
+        now
+    )
+    .execute(app.get_db())
+    .await?;
+
+    let character = sqlx::query!("SELECT name FROM character WHERE id=$1", character_id)
+        .fetch_one(app.get_db())
+        .await?;
+
+    app.sse_client
+        .submit(vec![Event::new_json(
+            "bans",
+            "ban_appeal",
+            &AppealNotification {
+                ban_id,
+                character: Character {
+                    id: character_id,
+                    name: character.name,
+                    corporation_id: None,
+                },
+            },
+        )])
+        .await?;
+
+    Ok("Ok")
+}
+
+#[derive(Debug, Serialize)]
+struct Appeal {
+    id: i64,
+    ban: Ban,
+    character: Character,
+    appeal: String,
+    submitted_at: i64,
+    state: String,
+    reviewed_by: Option<Character>,
+    reviewed_at: Option<i64>,
+    review_comment: Option<String>,
+}
+
+#[get("/api/v2/bans/appeals")]
+async fn list_appeals(
+    account: AuthenticatedAccount,
+    app: &rocket::State<Application>,
+) -> Result<Json<Vec<Appeal>>, Madness> {
+    account.require_access("bans-manage")?;
+
+    let rows = sqlx::query!(
+        "SELECT
+            ban_appeal.id,
+            ban_appeal.appeal,
+            ban_appeal.submitted_at,
+            ban_appeal.state,
+            ban_appeal.reviewed_at,
+            ban_appeal.review_comment,
+            pilot.id AS \"character_id\",
+            pilot.name AS \"character_name\",
+            reviewer.id AS \"reviewed_by_id?\",
+            reviewer.name AS \"reviewed_by_name?\",
+            ban.id AS \"ban_id\",
+            ban.entity_id,
+            ban.entity_name,
+            ban.entity_type,
+            ban.issued_at,
+            ban.public_reason,
+            ban.reason,
+            ban.revoked_at,
+            issuer.id AS \"issued_by_id\",
+            issuer.name AS \"issued_by_name\"
+        FROM
+            ban_appeal
+        JOIN
+            ban ON ban.id=ban_appeal.ban_id
+        JOIN
+            character AS pilot ON pilot.id=ban_appeal.character_id
+        JOIN
+            character AS issuer ON issuer.id=ban.issued_by
+        LEFT JOIN
+            character AS reviewer ON reviewer.id=ban_appeal.reviewed_by
+        ORDER BY
+            ban_appeal.state='pending' DESC, ban_appeal.submitted_at DESC"
+    )
+    .fetch_all(app.get_db())
+    .await?;
+
+    let appeals = rows
+        .into_iter()
+        .map(|appeal| Appeal {
+            id: appeal.id,
+            ban: Ban {
+                id: Some(appeal.ban_id),
+                entity: Some(Entity {
+                    id: appeal.entity_id,
+                    name: appeal.entity_name,
+                    category: appeal.entity_type,
+                }),
+                issued_at: Some(appeal.issued_at),
+                issued_by: Some(Character {
+                    id: appeal.issued_by_id,
+                    name: appeal.issued_by_name,
+                    corporation_id: None,
+                }),
+                reason: appeal.reason,
+                public_reason: appeal.public_reason,
+                revoked_at: appeal.revoked_at,
+                revoked_by: None,
+            },
+            character: Character {
+                id: appeal.character_id,
+                name: appeal.character_name,
+                corporation_id: None,
+            },
+            appeal: appeal.appeal,
+            submitted_at: appeal.submitted_at,
+            state: appeal.state,
+            reviewed_by: match (appeal.reviewed_by_id, appeal.reviewed_by_name) {
+                (Some(id), Some(name)) => Some(Character {
+                    id,
+                    name,
+                    corporation_id: None,
+                }),
+                _ => None,
+            },
+            reviewed_at: appeal.reviewed_at,
+            review_comment: appeal.review_comment,
+        })
+        .collect();
+
+    Ok(Json(appeals))
+}
+
+#[derive(Deserialize)]
+struct AppealReview {
+    accepted: bool,
+    comment: Option<String>,
+}
+
+#[post("/api/v2/bans/appeals/<appeal_id>", data = "<req_body>")]
+async fn review_appeal(
+    account: AuthenticatedAccount,
+    app: &rocket::State<Application>,
+    appeal_id: i64,
+    req_body: Json<AppealReview>,
+) -> Result<&'static str, Madness> {
+    account.require_access("bans-manage")?;
+
+    let appeal = match sqlx::query!(
+        "SELECT ban_id, state FROM ban_appeal WHERE id=$1",
+        appeal_id
+    )
+    .fetch_optional(app.get_db())
+    .await?
+    {
+        Some(appeal) => appeal,
+        None => return Err(Madness::NotFound("Appeal not found")),
+    };
+
+    if appeal.state != "pending" {
+        return Err(Madness::BadRequest(
+            "This appeal has already been reviewed".to_string(),
+        ));
+    }
+
+    let now = Utc::now().timestamp();
+    let state = if req_body.accepted { "accepted" } else { "rejected" };
+
+    let mut tx = app.get_db().begin().await?;
+    sqlx::query!(
+        "UPDATE ban_appeal SET state=$1, reviewed_by=$2, reviewed_at=$3, review_comment=$4 WHERE id=$5",
+        state,
+        account.id,
+        now,
+        req_body.comment,
+        appeal_id
+    )
+    .execute(&mut tx)
+    .await?;
+
+    // An accepted appeal lifts the ban, the same way revoking it would
+    if req_body.accepted {
+        sqlx::query!(
+            "UPDATE ban SET revoked_at=$1, revoked_by=$2 WHERE id=$3 AND (revoked_at IS NULL OR revoked_at > $1)",
+            now,
+            account.id,
+            appeal.ban_id
+        )
+        .execute(&mut tx)
+        .await?;
+    }
+
+    audit::record(
+        &mut tx,
+        AuditEntry::new(account.id, "ban-appeal-review", "ban_appeal", Some(appeal_id))
+            .before(json!({ "state": appeal.state }))
+            .after(json!({ "state": state, "comment": req_body.comment })),
+    )
+    .await?;
+    tx.commit().await?;
+
+    Ok("Ok")
+}
+
 pub fn routes() -> Vec<rocket::Route> {
     routes![
         list,              //  GET     /api/v2/bans
         create,            //  POST    /api/v2/bans
         character_history, //  GET     /api/v2/bans/<character_id>
         update,            //  PUT     /api/v2/bans/<ban_id>
-        revoke             //  DELETE  /api/v2/bans/<ban_id>
+        revoke,            //  DELETE  /api/v2/bans/<ban_id>
+        entity_history,    //  GET     /api/v2/bans/history/<entity_type>/<entity_id>
+        account_ban_alts,  //  GET     /api/v2/bans/accounts
+        evasion_flags,     //  GET     /api/v2/bans/evasion/<character_id>
+        submit_appeal,     //  POST    /api/v2/bans/appeals
+        list_appeals,      //  GET     /api/v2/bans/appeals
+        review_appeal      //  POST    /api/v2/bans/appeals/<appeal_id>
     ]
 }
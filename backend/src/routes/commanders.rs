@@ -1,18 +1,22 @@
-use std::cmp::Ordering;
-
 use crate::{
     app::Application,
-    core::auth::{get_access_keys, AuthenticatedAccount},
+    core::{
+        audit::{self, AuditEntry},
+        auth::{revoke_sessions, AuthenticatedAccount},
+    },
     util::madness::Madness,
 };
 
 use rocket::serde::json::Json;
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 
 #[derive(Deserialize)]
 struct RequestPayload {
     character_id: Option<i64>,
     role: String,
+    expires_at: Option<i64>,
+    reason: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -34,6 +38,8 @@ struct Commander {
     role: String,
     granted_by: Character,
     granted_at: i64,
+    expires_at: Option<i64>,
+    reason: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -73,6 +79,8 @@ async fn list(
         "SELECT
         role,
         granted_at,
+        expires_at,
+        reason,
         fc.id AS \"id\",
         fc.name AS \"name\",
         a.id AS \"admin_id\",
@@ -98,6 +106,8 @@ async fn list(
                 name: cmdr.admin_name.unwrap(),
             },
             granted_at: cmdr.granted_at.unwrap(),
+            expires_at: cmdr.expires_at,
+            reason: cmdr.reason,
         })
         .collect();
 
@@ -125,7 +135,9 @@ async fn assign(
     }
 
     // Ensure the requested role exists
-    let role = get_access_keys(&body.role);
+    let role = sqlx::query!("SELECT name FROM role WHERE name=$1", body.role)
+        .fetch_optional(app.get_db())
+        .await?;
     if role.is_none() {
         return Err(Madness::BadRequest(format!(
             "The FC rank \"{}\" does not exist",
@@ -142,33 +154,55 @@ async fn assign(
         )));
     }
 
+    // Grants can be time limited, e.g. for guest FCs
+    let now = chrono::Utc::now().timestamp();
+    if body.expires_at.map(|expires_at| expires_at <= now).unwrap_or(false) {
+        return Err(Madness::BadRequest(
+            "The expiry date must be in the future".to_string(),
+        ));
+    }
+
     let character_id = body.character_id.unwrap();
     if let Some(character) = sqlx::query!("SELECT * FROM character WHERE id=$1", character_id)
         .fetch_optional(app.get_db())
         .await?
     {
-        // Ensure the character doesn't already have a role - Character <-> Admin is a 1 to 1 relationship
-        if let Some(_) = sqlx::query!("SELECT * FROM admin WHERE character_id=$1", character_id)
-            .fetch_optional(app.get_db())
-            .await?
+        // Characters can hold several roles, but each of them only once
+        if let Some(_) = sqlx::query!(
+            "SELECT * FROM admin WHERE character_id=$1 AND role=$2",
+            character_id,
+            body.role
+        )
+        .fetch_optional(app.get_db())
+        .await?
         {
             return Err(Madness::BadRequest(format!(
-                "Cannot assign \"{}\" to {} as they already have a role",
+                "Cannot assign \"{}\" to {} as they already have this role",
                 body.role, character.name
             )));
         }
 
-        let now = chrono::Utc::now().timestamp();
+        let mut tx = app.get_db().begin().await?;
         sqlx::query!(
-            "INSERT INTO admin VALUES ($1, $2, $3, $4)",
+            "INSERT INTO admin (character_id, role, granted_at, granted_by_id, expires_at, reason) VALUES ($1, $2, $3, $4, $5, $6)",
             character_id,
             body.role,
             now,
-            account.id
+            account.id,
+            body.expires_at,
+            body.reason
         )
-        .execute(app.get_db())
+        .execute(&mut tx)
         .await?;
 
+        audit::record(
+            &mut tx,
+            AuditEntry::new(account.id, "commander-assign", "character", Some(character_id))
+                .after(json!({ "role": body.role, "expires_at": body.expires_at, "reason": body.reason })),
+        )
+        .await?;
+        tx.commit().await?;
+
         return Ok("Ok");
     }
 
@@ -180,8 +214,15 @@ async fn assign(
 async fn public_directory(
     app: &rocket::State<Application>,
 ) -> Result<Json<Vec<CharacterWithRole>>, Madness> {
+    // Commanders with several roles are listed once, under their highest role
     let team = sqlx::query!(
-        "SELECT role, fc.id, fc.name FROM admin JOIN character AS fc ON character_id = fc.id WHERE role != 'Wiki Team' ORDER BY role"
+        "SELECT role AS \"role!\", id AS \"id!\", name AS \"name!\" FROM (
+            SELECT DISTINCT ON (fc.id) admin.role, fc.id, fc.name FROM admin
+            JOIN character AS fc ON character_id = fc.id
+            JOIN role ON role.name = admin.role
+            WHERE admin.role != 'Wiki Team'
+            ORDER BY fc.id, role.sort_order ASC
+        ) AS team ORDER BY role"
     )
     .fetch_all(app.get_db())
     .await?
@@ -193,34 +234,23 @@ async fn public_directory(
 }
 
 #[get("/api/commanders/roles")]
-async fn assignable(account: AuthenticatedAccount) -> Result<Json<Vec<&'static str>>, Madness> {
+async fn assignable(
+    account: AuthenticatedAccount,
+    app: &rocket::State<Application>,
+) -> Result<Json<Vec<String>>, Madness> {
     account.require_access("commanders-manage")?;
 
-    let role_order = vec!["Wiki Team", "Trainee", "FC", "Instructor", "Leadership"];
-
-    let mut options = Vec::new();
-    for scope in account.access.into_iter() {
-        if scope.contains("commanders-manage:") {
-            // 14 is the index of ":".
-            let (_, b) = scope.split_at(18);
-
-            options.push(b);
-        }
-    }
-
-    options.sort_by(|a, b| {
-        if let Some(a) = role_order.iter().position(|&r| &r == a) {
-            if let Some(b) = role_order.iter().position(|&r| &r == b) {
-                if a < b {
-                    return Ordering::Less;
-                } else {
-                    return Ordering::Greater;
-                }
-            }
-        }
-
-        Ordering::Equal
-    });
+    let options = sqlx::query!("SELECT name FROM role ORDER BY sort_order ASC")
+        .fetch_all(app.get_db())
+        .await?
+        .into_iter()
+        .map(|role| role.name)
+        .filter(|role| {
+            account
+                .access
+                .contains(&format!("commanders-manage:{}", role))
+        })
+        .collect();
 
     Ok(Json(options))
 }
@@ -233,9 +263,14 @@ async fn lookup(
 ) -> Result<String, Madness> {
     account.require_access("commanders-manage")?;
 
-    if let Some(role) = sqlx::query!("Select * FROM admin WHERE character_id=$1", character_id)
-        .fetch_optional(app.get_db())
-        .await?
+    // Characters with several roles are shown with their highest one
+    if let Some(role) = sqlx::query!(
+        "SELECT role FROM admin JOIN role ON role.name=admin.role WHERE character_id=$1
+        ORDER BY role.sort_order DESC LIMIT 1",
+        character_id
+    )
+    .fetch_optional(app.get_db())
+    .await?
     {
         return Ok(role.role);
     }
@@ -244,11 +279,12 @@ async fn lookup(
     return Err(Madness::NotFound(""));
 }
 
-#[delete("/api/commanders/<character_id>")]
+#[delete("/api/commanders/<character_id>?<role>")]
 async fn revoke(
     account: AuthenticatedAccount,
     app: &rocket::State<Application>,
     character_id: i64,
+    role: Option<String>,
 ) -> Result<&'static str, Madness> {
     account.require_access("commanders-manage")?;
 
@@ -259,25 +295,50 @@ async fn revoke(
         )));
     }
 
-    // Check the target user has a role. If they do not return a 200, if they do...
-    if let Some(role) = sqlx::query!("SELECT * FROM admin WHERE character_id=$1", character_id)
-        .fetch_optional(app.get_db())
+    // Without a role every role the target holds is revoked. If they have none, return a 200
+    let roles: Vec<String> = sqlx::query!("SELECT role FROM admin WHERE character_id=$1", character_id)
+        .fetch_all(app.get_db())
         .await?
-    {
-        // Ensure the authenticated user is allowed to revoke the role
-        let required_scope = format!("commanders-manage:{}", role.role);
+        .into_iter()
+        .map(|admin| admin.role)
+        .filter(|held| role.as_ref().map(|role| role == held).unwrap_or(true))
+        .collect();
+
+    // Ensure the authenticated user is allowed to revoke the roles
+    for role in &roles {
+        let required_scope = format!("commanders-manage:{}", role);
         if !account.access.contains(&required_scope) {
             return Err(Madness::Forbidden(format!(
                 "You do not have permission to revoke the role \"{}\"",
-                role.role
+                role
             )));
         }
+    }
 
-        // Revoke the role
-        sqlx::query!("DELETE FROM admin WHERE character_id=$1", character_id)
-            .execute(app.get_db())
-            .await?;
-    };
+    // Revoke the roles
+    let mut tx = app.get_db().begin().await?;
+    sqlx::query!(
+        "DELETE FROM admin WHERE character_id=$1 AND role = ANY($2)",
+        character_id,
+        &roles
+    )
+    .execute(&mut tx)
+    .await?;
+
+    if !roles.is_empty() {
+        audit::record(
+            &mut tx,
+            AuditEntry::new(account.id, "commander-revoke", "character", Some(character_id))
+                .before(json!({ "roles": roles })),
+        )
+        .await?;
+    }
+    tx.commit().await?;
+
+    if !roles.is_empty() {
+        // Browsers logged in with the old role have to log in again
+        revoke_sessions(app.get_db(), character_id).await?;
+    }
 
     return Ok("Ok");
 }
@@ -289,6 +350,6 @@ pub fn routes() -> Vec<rocket::Route> {
         public_directory,   // GET      /api/commanders/directory
         assignable,         // GET      /api/commanders/roles
         lookup,             // GET      /api/commanders/<character_id>
-        revoke              // DELETE   /api/commanders/<character_id>
+        revoke              // DELETE   /api/commanders/<character_id>?<role>
     ]
 }
@@ -1,11 +1,14 @@
-use eve_data_core::Fitting;
+use eve_data_core::{Fitting, TypeID};
 use rocket::serde::json::Json;
 use serde::{Deserialize, Serialize};
 
 use crate::{
     app::Application,
-    core::auth::AuthenticatedAccount,
-    data::{implants, skills},
+    core::auth::{authorize_character, AuthenticatedAccount},
+    data::{
+        implants, saved_fittings,
+        skills::{self, Skills},
+    },
     tdf::{
         self,
         fitcheck::{Output, PilotData, PubAnalysis},
@@ -22,10 +25,60 @@ struct XupRequest {
 #[derive(Debug, Serialize)]
 pub struct FitResult {
     pub approved: bool,
+    pub warnings: Vec<String>,
     pub fit_analysis: Option<PubAnalysis>,
     pub dna: String,
 }
 
+#[derive(Debug, Serialize)]
+struct SavedFitResult {
+    fitting_id: i64,
+    name: String,
+    error: Option<String>,
+    result: Option<FitResult>,
+}
+
+/// Everything the fit checker needs to know about a pilot
+struct PilotInfo {
+    implants: Vec<TypeID>,
+    skills: Skills,
+    badges: Vec<String>,
+}
+
+impl PilotInfo {
+    async fn load(app: &Application, character_id: i64) -> Result<PilotInfo, Madness> {
+        let badges = sqlx::query!(
+            "SELECT badge.name FROM badge JOIN badge_assignment ON id=badge_assignment.BadgeId WHERE badge_assignment.CharacterId=$1", character_id
+        )
+        .fetch_all(app.get_db())
+        .await?
+        .into_iter()
+        .map(|b| b.name)
+        .collect();
+
+        Ok(PilotInfo {
+            implants: implants::get_implants(app, character_id).await?,
+            skills: skills::load_skills(&app.esi_client, app.get_db(), character_id).await?,
+            badges,
+        })
+    }
+
+    // Time in fleet is left out, a fit check is not an x-up
+    fn pilot_data<'a>(
+        &'a self,
+        account: &'a AuthenticatedAccount,
+        character_id: &'a i64,
+    ) -> PilotData<'a> {
+        PilotData {
+            implants: &self.implants,
+            time_in_fleet: 0,
+            skills: &self.skills,
+            access_keys: &account.access,
+            id: character_id,
+        }
+    }
+}
+
 #[post("/api/fit-check", data = "<input>")]
 async fn fitcheck(
     account: AuthenticatedAccount,
@@ -34,29 +87,13 @@ async fn fitcheck(
 ) -> Result<Json<Vec<FitResult>>, Madness> {
     let fits: Vec<Fitting> = Fitting::from_eft(&input.eft)?;
 
-    let pilot: PilotData = PilotData {
-        implants: &implants::get_implants(app, input.character_id).await?,
-        time_in_fleet: 0,
-        skills: &skills::load_skills(&app.esi_client, app.get_db(), input.character_id).await?,
-        access_keys: account.access,
-        id: &input.character_id,
-    };
-
-    let badges: Vec<String> = sqlx::query!(
-        "SELECT badge.name FROM badge JOIN badge_assignment ON id=badge_assignment.BadgeId WHERE badge_assignment.CharacterId=$1", input.character_id
-    )
-    .fetch_all(app.get_db())
-    .await?
-    .into_iter()
-    .map(|b| {
-        b.name
-    })
-    .collect();
+    let info = PilotInfo::load(app, input.character_id).await?;
+    let pilot = info.pilot_data(&account, &input.character_id);
 
     let mut result = Vec::new();
 
     for fit in fits {
-        let fit_checked: Output = tdf::fitcheck::FitChecker::check(&pilot, &fit, &badges).await?;
+        let fit_checked: Output = tdf::fitcheck::FitChecker::check(&pilot, &fit, &info.badges).await?;
 
         if let Some(error) = fit_checked.errors.into_iter().next() {
             return Err(Madness::BadRequest(error));
@@ -64,6 +101,7 @@ async fn fitcheck(
 
         result.push(FitResult {
             approved: fit_checked.approved,
+            warnings: fit_checked.warnings,
             fit_analysis: fit_checked.analysis,
             dna: fit.to_dna()?,
         });
@@ -72,8 +110,55 @@ async fn fitcheck(
     Ok(Json(result))
 }
 
+#[get("/api/fit-check/saved?<character_id>")]
+async fn saved_fittings(
+    account: AuthenticatedAccount,
+    app: &rocket::State<Application>,
+    character_id: i64,
+) -> Result<Json<Vec<SavedFitResult>>, Madness> {
+    authorize_character(app.get_db(), &account, character_id, None).await?;
+
+    let fittings = saved_fittings::get_saved_fittings(&app.esi_client, character_id).await?;
+
+    let info = PilotInfo::load(app, character_id).await?;
+    let pilot = info.pilot_data(&account, &character_id);
+
+    let mut result = Vec::new();
+    for saved in fittings {
+        // Saved fits can hold anything, a broken one shouldn't hide the others
+        let fit = match saved.to_fitting().and_then(|fit| fit.validate().map(|_| fit)) {
+            Ok(fit) => fit,
+            Err(e) => {
+                result.push(SavedFitResult {
+                    fitting_id: saved.fitting_id,
+                    name: saved.name,
+                    error: Some(e.to_string()),
+                    result: None,
+                });
+                continue;
+            }
+        };
+
+        let fit_checked: Output = tdf::fitcheck::FitChecker::check(&pilot, &fit, &info.badges).await?;
+        result.push(SavedFitResult {
+            fitting_id: saved.fitting_id,
+            name: saved.name,
+            error: fit_checked.errors.into_iter().next(),
+            result: Some(FitResult {
+                approved: fit_checked.approved,
+                warnings: fit_checked.warnings,
+                fit_analysis: fit_checked.analysis,
+                dna: fit.to_dna()?,
+            }),
+        });
+    }
+
+    Ok(Json(result))
+}
+
 pub fn routes() -> Vec<rocket::Route> {
     routes![
-        fitcheck    // POST /api/fit-check
+        fitcheck,       // POST /api/fit-check
+        saved_fittings, // GET  /api/fit-check/saved
     ]
 }
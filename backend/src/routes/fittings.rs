@@ -1,7 +1,7 @@
 use crate::data::yamlhelper;
 use crate::util::madness::Madness;
-use eve_data_core::TypeID;
-use rocket::serde::json::Json;
+use eve_data_core::{Fitting, TypeID};
+use rocket::{http::ContentType, serde::json::Json};
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 
@@ -9,6 +9,7 @@ use std::collections::BTreeMap;
 pub struct DNAFitting {
     pub name: String,
     pub dna: String,
+    pub eft: String,
 }
 #[derive(Debug, Serialize)]
 struct FittingResponse {
@@ -43,9 +44,11 @@ async fn fittings() -> Result<Json<FittingResponse>, Madness> {
         }
         let fitname = fit.name.clone();
         let dna = fit.fit.to_dna().unwrap();
+        let eft = fit.fit.to_eft(&fit.name)?;
         fittingformatted.entry(id).or_insert_with(|| DNAFitting {
             name: fitname,
             dna: dna.clone(),
+            eft,
         });
         id += 1;
     }
@@ -68,6 +71,21 @@ async fn fittings() -> Result<Json<FittingResponse>, Madness> {
     }))
 }
 
+#[get("/api/fittings/xml")]
+async fn fittings_xml() -> Result<(ContentType, String), Madness> {
+    let fits: Vec<(&str, &Fitting)> = crate::data::fits::get_fits()
+        .values()
+        .flatten()
+        .filter(|fit| !fit.hidden)
+        .map(|fit| (fit.name.as_str(), &fit.fit))
+        .collect();
+
+    Ok((ContentType::XML, Fitting::to_xml(&fits)?))
+}
+
 pub fn routes() -> Vec<rocket::Route> {
-    routes![fittings]
+    routes![
+        fittings,     //  GET     /api/fittings
+        fittings_xml, //  GET     /api/fittings/xml
+    ]
 }
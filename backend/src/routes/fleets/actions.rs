@@ -1,8 +1,12 @@
+use crate::core::audit::{self, AuditEntry};
 use crate::core::esi::ESIScope;
+use crate::core::time_in_fleet::TimeInFleetService;
+use crate::data::character;
 use crate::{core::auth::AuthenticatedAccount, app::Application, util::madness::Madness};
 use eve_data_core::TypeDB;
 use crate::core::sse::Event;
 use serde::Serialize;
+use serde_json::json;
 use std::collections::HashMap;
 
 #[derive(Debug, Serialize)]
@@ -61,9 +65,14 @@ async fn delete_fleet(
         .execute(&mut tx)
         .await?;
 
-    sqlx::query!("UPDATE fleet_activity SET has_left=true WHERE fleet_id=$1 AND has_left=false", fleet_id)
-        .execute(&mut tx)
-        .await?;
+    TimeInFleetService::close_fleet(&mut tx, fleet_id, chrono::Utc::now().timestamp()).await?;
+
+    audit::record(
+        &mut tx,
+        AuditEntry::new(account.id, "fleet-delete", "fleet", Some(fleet_id))
+            .before(json!({ "boss_id": fleet.boss_id })),
+    )
+    .await?;
 
     tx.commit().await?;
 
@@ -74,6 +83,14 @@ async fn delete_fleet(
     )])
     .await?;
 
+    if app.webhook_client.is_enabled("fleet_closed") {
+        let fc = character::name_of(app.get_db(), account.id).await?;
+        app.webhook_client.notify(
+            "fleet_closed",
+            &[("fc", fc.as_str()), ("fleet_id", fleet_id.to_string().as_str())],
+        );
+    }
+
     app.sse_client.submit(vec![Event::new_json(
         "waitlist",
         "fleets_updated",
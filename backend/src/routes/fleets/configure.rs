@@ -1,10 +1,10 @@
 use crate::{
     app::Application,
-    core::{auth::{AuthenticatedAccount, authorize_character}, esi::{ESIScope, ESIError, self}, sse::Event},
+    core::{audit::{self, AuditEntry}, auth::{AuthenticatedAccount, authorize_character}, esi::{ESIScope, ESIError, self}, sse::Event},
     util::{
         madness::Madness,
         types::{Character, Empty, System},
-    }, data::fleets::{self as fleet_data, FleetInfo},
+    }, data::{character, fleets::{self as fleet_data, FleetInfo}},
 };
 
 use eve_data_core::TypeDB;
@@ -119,6 +119,9 @@ async fn close_all(
             .execute(&mut tx)
             .await?;
 
+        audit::record(&mut tx, AuditEntry::new(account.id, "fleet-delete", "fleet", Some(fleet.id)))
+            .await?;
+
         tx.commit().await?;
     }
 
@@ -129,6 +132,11 @@ async fn close_all(
     )])
     .await?;
 
+    if app.webhook_client.is_enabled("fleet_closed") {
+        let fc = character::name_of(app.get_db(), account.id).await?;
+        app.webhook_client.notify("fleet_closed", &[("fc", fc.as_str())]);
+    }
+
     app.sse_client.submit(vec![Event::new_json(
         "waitlist",
         "fleets_updated",
@@ -278,6 +286,14 @@ async fn register(
     // All squads are created and mapped, so it's time to commit our DB changes
     tx.commit().await?;
 
+    if app.webhook_client.is_enabled("fleet_opened") {
+        let boss = character::name_of(app.get_db(), basic_info.fleet_boss_id).await?;
+        app.webhook_client.notify(
+            "fleet_opened",
+            &[("fc", boss.as_str()), ("fleet_id", basic_info.fleet_id.to_string().as_str())],
+        );
+    }
+
     if body.default_motd {
         fleet_data::set_default_motd(app.get_db(), &app.esi_client, &basic_info).await?;
     }
@@ -1,5 +1,5 @@
 use crate::util::types::{Character, System};
-use crate::{core::auth::AuthenticatedAccount, util::madness::Madness, app::Application};
+use crate::{core::auth::AuthenticatedAccount, data::character, util::madness::Madness, app::Application};
 use eve_data_core::TypeDB;
 use rocket::serde::json::Json;
 use serde::{Deserialize, Serialize};
@@ -94,12 +94,21 @@ async fn set_boss(
 ) -> Result<&'static str, Madness> {
     account.require_access("fleet-view")?;
 
-    if let Some(_) = sqlx::query!("SELECT * FROM fleet WHERE id=$1", fleet_id)
+    if let Some(fleet) = sqlx::query!("SELECT * FROM fleet WHERE id=$1", fleet_id)
     .fetch_optional(app.get_db())
     .await? {
         sqlx::query!("UPDATE fleet SET boss_id=$1, error_count=0 WHERE id=$2", body.fleet_boss, fleet_id)
         .execute(app.get_db())
         .await?;
+
+        if fleet.boss_id != body.fleet_boss && app.webhook_client.is_enabled("fc_handover") {
+            let fc = character::name_of(app.get_db(), fleet.boss_id).await?;
+            let boss = character::name_of(app.get_db(), body.fleet_boss).await?;
+            app.webhook_client.notify(
+                "fc_handover",
+                &[("fc", fc.as_str()), ("boss", boss.as_str()), ("fleet_id", fleet_id.to_string().as_str())],
+            );
+        }
     }
 
     notify::fleets_updated(&app, "fleet_settings", Some(fleet_id)).await?;
@@ -117,12 +126,22 @@ async fn set_visibility(
     account.require_access("fleet-view")?;
 
 
-    if let Some(_) = sqlx::query!("SELECT * FROM fleet WHERE id=$1", fleet_id)
+    if let Some(fleet) = sqlx::query!("SELECT * FROM fleet WHERE id=$1", fleet_id)
         .fetch_optional(app.get_db())
         .await? {
+            // The waitlist opens when the first fleet becomes visible
+            let was_open = sqlx::query!("SELECT id FROM fleet WHERE visible=true")
+                .fetch_optional(app.get_db())
+                .await?
+                .is_some();
+
             sqlx::query!("UPDATE fleet SET visible=$1 WHERE id=$2", body.visible, fleet_id)
             .execute(app.get_db())
             .await?;
+
+            if body.visible && !fleet.visible && !was_open {
+                app.webhook_client.notify("waitlist_opened", &[("fleet_id", fleet_id.to_string().as_str())]);
+            }
         }
 
     notify::fleets_updated(&app, "fleet_settings", Some(fleet_id)).await?;
@@ -55,26 +55,12 @@ struct WaitlistEntryFit {
     is_alt: bool,
 }
 
-async fn get_time_in_fleet(db: &crate::DB, character_id: i64) -> Result<FleetHours, sqlx::Error> {
-    let result = sqlx::query!(
-        "select
-        hull,
-        cast(sum(last_seen - first_seen) as bigint) as seconds
-        from fleet_activity fa
-        left join alt_character ac on ac.alt_id = fa.character_id
-        where ac.account_id = $1 or fa.character_id = $1
-        group by hull"
-    , character_id)
-    .fetch_all(db)
-    .await?;
-
-    let total_time: i64 = result.iter().map(|rec| rec.seconds.unwrap()).sum();
-    let bastion_time: i64 = result.iter().filter(|r| r.hull == 28659 || r.hull == 28661).map(|rec| rec.seconds.unwrap()).sum();
-    let fleet_time = FleetHours{
-        total: total_time / 3600,
-        bastion: bastion_time / 3600
-    };
-    Ok(fleet_time)
+async fn get_time_in_fleet(app: &Application, account_id: i64) -> Result<FleetHours, sqlx::Error> {
+    let time = app.time_in_fleet.account_time(account_id).await?;
+    Ok(FleetHours {
+        total: time.total / 3600,
+        bastion: time.hull_seconds(&[type_id!("Paladin"), type_id!("Kronos")]) / 3600,
+    })
 }
 
 
@@ -107,6 +93,7 @@ async fn fleet_waitlist(
                 wef.tags wef_tags,
                 wef.fit_analysis wef_fit_analysis,
 				wef.is_alt wef_is_alt,
+                EXISTS(SELECT 1 FROM evasion_flag WHERE evasion_flag.character_id = wef.character_id) AS \"wef_possible_evasion!\",
                 char_wef.id char_wef_id,
                 char_wef.name char_wef_name,
                 char_we.id char_we_id,
@@ -155,7 +142,7 @@ async fn fleet_waitlist(
                     },
                     joined_at: record.we_joined_at,
                     can_remove: x_is_ours || account.access.contains("waitlist-manage"),
-                    fleet_time: get_time_in_fleet(app.get_db(), record.char_we_id).await?
+                    fleet_time: get_time_in_fleet(app, record.char_we_id).await?
                 });
             },
             Entry::Occupied(_entry) => (),
@@ -205,6 +192,9 @@ async fn fleet_waitlist(
             this_fit.hours_in_fleet = Some(record.wef_cached_time_in_fleet / 3600);
             this_fit.review_comment = record.wef_review_comment;
             this_fit.tags = tags.collect();
+            if record.wef_possible_evasion && account.access.contains("waitlist-view") {
+                this_fit.tags.push("POSSIBLE-EVASION".to_string());
+            }
         } else {
             this_fit.tags = tags
                 .filter(|t| data::tags::public_tags().contains(t))
@@ -46,7 +46,6 @@ async fn fleet_history(
     )
     .await?;
 
-    let mut time_by_hull = HashMap::new();
     let activity = sqlx::query!(
         "SELECT hull, first_seen, last_seen FROM fleet_activity WHERE character_id=$1 ORDER BY first_seen DESC",
         character_id
@@ -59,7 +58,6 @@ async fn fleet_history(
     let mut entries = Vec::new();
     for (hull, first_seen, last_seen) in activity {
         let time_in_fleet = last_seen - first_seen;
-        *time_by_hull.entry(hull).or_insert(0) += time_in_fleet;
 
         entries.push(ActivityEntry {
             hull: Hull {
@@ -72,6 +70,7 @@ async fn fleet_history(
     }
 
     let mut summary = Vec::new();
+    let time_by_hull = app.time_in_fleet.character_time(character_id).await?.by_hull;
     for (hull, time_in_fleet) in time_by_hull {
         summary.push(ActivitySummaryEntry {
             hull: Hull {
@@ -1,4 +1,6 @@
+mod alts;
 mod announcements;
+mod audit;
 mod auth;
 mod badges;
 mod bans;
@@ -15,17 +17,22 @@ mod modules;
 mod notes;
 mod pilot;
 mod search;
+mod sessions;
 mod skillplans;
 mod reports;
+mod roles;
 mod skills;
 mod sse;
 mod statistics;
+mod tokens;
 mod waitlist;
 mod window;
 
 pub fn routes() -> Vec<rocket::Route> {
     [
+        alts::routes(),
         announcements::routes(),
+        audit::routes(),
         auth::routes(),
         sse::routes(),
         skills::routes(),
@@ -37,18 +44,21 @@ pub fn routes() -> Vec<rocket::Route> {
         commanders::routes(),
         modules::routes(),
         search::routes(),
+        sessions::routes(),
         categories::routes(),
         fleet::routes(),
         fleets::routes(),
         waitlist::routes(),
         statistics::routes(),
+        tokens::routes(),
         healthcheck::routes(),
         implants::routes(),
         notes::routes(),
         skillplans::routes(),
         fitcheck::routes(),
         fittings::routes(),
-        reports::routes()
+        reports::routes(),
+        roles::routes()
     ]
     .concat()
 }
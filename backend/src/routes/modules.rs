@@ -4,7 +4,11 @@ use eve_data_core::{TypeDB, TypeID};
 use rocket::serde::json::Json;
 use serde::Serialize;
 
-use crate::{core::auth::AuthenticatedAccount, util::madness::Madness};
+use crate::{
+    core::auth::AuthenticatedAccount,
+    data::variations::{self, TierDifference},
+    util::madness::Madness,
+};
 
 #[derive(Debug, Serialize)]
 struct Module {
@@ -67,6 +71,17 @@ fn preload() -> Json<&'static ModuleResponse> {
     Json(&PRELOAD)
 }
 
+#[get("/api/module/variations/report")]
+fn variations_report(account: AuthenticatedAccount) -> Result<Json<Vec<TierDifference>>, Madness> {
+    account.require_access("modules-manage")?;
+
+    Ok(Json(variations::report()?))
+}
+
 pub fn routes() -> Vec<rocket::Route> {
-    routes![module_info, preload]
+    routes![
+        module_info,       //  GET     /api/module/info
+        preload,           //  GET     /api/module/preload
+        variations_report, //  GET     /api/module/variations/report
+    ]
 }
@@ -1,11 +1,19 @@
 use rocket::serde::json::Json;
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 
 use crate::{
     app,
-    core::auth::AuthenticatedAccount,
-    util::{madness::Madness, types::Character},
+    core::{
+        audit::{self, AuditEntry},
+        auth::AuthenticatedAccount,
+    },
+    util::{
+        madness::Madness,
+        types::{Character, Hull},
+    },
 };
+use eve_data_core::{TypeDB, TypeID};
 
 #[derive(Debug, Serialize)]
 struct NotesListNote {
@@ -14,9 +22,19 @@ struct NotesListNote {
     note: String,
 }
 
+#[derive(Debug, Serialize)]
+struct PendingUpgrade {
+    hull: Hull,
+    hours: i64,
+    tag: String,
+    state: String,
+    logged_at: i64,
+}
+
 #[derive(Debug, Serialize)]
 struct NotesList {
     notes: Vec<NotesListNote>,
+    pending_upgrades: Vec<PendingUpgrade>,
 }
 
 #[get("/api/notes?<character_id>")]
@@ -50,7 +68,30 @@ async fn list_notes(
         })
         .collect();
 
-    Ok(Json(NotesList { notes }))
+    let mut pending_upgrades = Vec::new();
+    for upgrade in sqlx::query!(
+        "SELECT hull, hours, tag, state, logged_at FROM pending_upgrade WHERE character_id = $1 ORDER BY logged_at DESC",
+        character_id
+    )
+    .fetch_all(app.get_db())
+    .await?
+    {
+        pending_upgrades.push(PendingUpgrade {
+            hull: Hull {
+                id: upgrade.hull as TypeID,
+                name: TypeDB::name_of(upgrade.hull as TypeID)?,
+            },
+            hours: upgrade.hours,
+            tag: upgrade.tag,
+            state: upgrade.state,
+            logged_at: upgrade.logged_at,
+        });
+    }
+
+    Ok(Json(NotesList {
+        notes,
+        pending_upgrades,
+    }))
 }
 
 #[derive(Deserialize)]
@@ -72,6 +113,7 @@ async fn add_note(
     }
 
     let now = chrono::Utc::now().timestamp();
+    let mut tx = app.get_db().begin().await?;
     sqlx::query!(
         "INSERT INTO character_note (author_id, character_id, note, logged_at) VALUES ($1, $2, $3, $4)",
         account.id,
@@ -79,8 +121,16 @@ async fn add_note(
         input.note,
         now,
     )
-    .execute(app.get_db())
+    .execute(&mut tx)
+    .await?;
+
+    audit::record(
+        &mut tx,
+        AuditEntry::new(account.id, "note-add", "character", Some(input.character_id))
+            .after(json!({ "note": input.note })),
+    )
     .await?;
+    tx.commit().await?;
 
     Ok("OK")
 }
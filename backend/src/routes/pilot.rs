@@ -24,17 +24,12 @@ async fn pilot_info(
     let mut tags: Vec<String> = Vec::new();
 
     // Add the ACL tag to the array
-    if let Some(admin) = sqlx::query!("SELECT role FROM admin WHERE character_id=$1", character.id)
-        .fetch_optional(app.get_db())
-        .await?
-    {
-        let keys = get_access_keys(&admin.role).unwrap();
-        if keys.contains("waitlist-tag:HQ-FC") {
-            tags.push("HQ-FC".to_string());
-        } else if keys.contains("waitlist-tag:TRAINEE") {
-            tags.push("TRAINEE".to_string());
-        };
-    }
+    let keys = get_access_keys(app.get_db(), character.id).await?;
+    if keys.contains("waitlist-tag:HQ-FC") {
+        tags.push("HQ-FC".to_string());
+    } else if keys.contains("waitlist-tag:TRAINEE") {
+        tags.push("TRAINEE".to_string());
+    };
 
     // Add specialist badges to the tags array
     for badge in sqlx::query!("SELECT b.name from badge_assignment AS ba INNER JOIN badge AS b on b.id=ba.BadgeId WHERE ba.CharacterId=$1", character.id)
@@ -25,6 +25,8 @@ async fn get_reports(
 ) -> Result<Json<Vec<ReportRow>>, Madness> {
     account.require_access("reports-view")?;
 
+    // Totals come from the fleet_time cache, which counts every stint in fleet. Short stints
+    // used to be left out here, last_seen counts them too so a row stays consistent.
     let activity = sqlx::query_as!(
         ReportRow,
         "SELECT 
@@ -32,20 +34,21 @@ async fn get_reports(
         c.id AS \"character_id!\", 
         c.name AS \"name!\", 
         'Fleet Boss' AS role, 
-        MAX(fa.last_seen) AS \"last_seen\", 
-        SUM(fa.last_seen - fa.first_seen) AS \"seconds_last_month\" 
-        FROM character AS c JOIN \"admin\" AS a on a.character_id = c.id 
-        LEFT JOIN fleet_activity AS fa ON fa.character_id = c.id AND fa.is_boss = 'true' AND (fa.last_seen - fa.first_seen) > 300 
+        (SELECT MAX(fa.last_seen) FROM fleet_activity AS fa WHERE fa.character_id = c.id AND fa.is_boss = 'true') AS \"last_seen\", 
+        SUM(ft.boss_seconds) AS \"seconds_last_month\" 
+        FROM character AS c 
+        LEFT JOIN fleet_time AS ft ON ft.character_id = c.id 
+        WHERE EXISTS (SELECT 1 FROM \"admin\" AS a WHERE a.character_id = c.id) 
         GROUP BY 
         c.id, 
         c.name UNION SELECT -1 * c.id AS \"id!\", 
         c.id AS \"character_id!\", 
         c.name AS \"name!\", 
         'Logi' AS role, 
-        MAX(fa.last_seen) AS \"last_seen\", 
-        SUM(fa.last_seen - fa.first_seen) AS \"seconds_last_month\" 
+        (SELECT MAX(fa.last_seen) FROM fleet_activity AS fa WHERE fa.character_id = c.id AND (fa.hull=$1 OR fa.hull=$2)) AS \"last_seen\", 
+        SUM(ft.seconds) AS \"seconds_last_month\" 
         FROM character AS c JOIN badge_assignment AS ba ON ba.characterId = c.id JOIN badge AS b ON b.id = ba.badgeID AND b.name = 'LOGI' 
-        LEFT JOIN fleet_activity AS fa ON fa.character_id = c.id AND (fa.hull=$1 OR fa.hull=$2) AND (fa.last_seen - fa.first_seen) > 300 
+        LEFT JOIN fleet_time AS ft ON ft.character_id = c.id AND (ft.hull=$1 OR ft.hull=$2) 
         GROUP BY c.id, c.name",
         type_id!("Nestor"),
         type_id!("Oneiros"),
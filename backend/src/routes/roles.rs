@@ -0,0 +1,319 @@
+use std::collections::BTreeMap;
+
+use rocket::serde::json::Json;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    app::Application,
+    core::{
+        audit::{self, AuditEntry},
+        auth::AuthenticatedAccount,
+    },
+    util::madness::Madness,
+};
+
+#[derive(Debug, Serialize)]
+struct Role {
+    name: String,
+    sort_order: i32,
+    member_count: i64,
+    permissions: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RoleRequest {
+    name: String,
+    sort_order: i32,
+    permissions: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RoleUpdate {
+    sort_order: i32,
+    permissions: Vec<String>,
+}
+
+/// Access keys the code checks for, as seeded by the role migrations. `commanders-manage:<role>`
+/// is also accepted for any existing role.
+const ACCESS_KEYS: &[&str] = &[
+    "audit-view",
+    "badges-manage",
+    "bans-manage",
+    "commanders-manage",
+    "commanders-view",
+    "fit-history-view",
+    "fit-view",
+    "fleet-activity-view",
+    "fleet-admin",
+    "fleet-configure",
+    "fleet-history-view",
+    "fleet-invite",
+    "fleet-view",
+    "modules-manage",
+    "notes-add",
+    "notes-view",
+    "pilot-view",
+    "reports-view",
+    "roles-manage",
+    "search",
+    "skill-history-view",
+    "skill-view",
+    "stats-view",
+    "waitlist-edit",
+    "waitlist-manage",
+    "waitlist-tag:HQ-FC",
+    "waitlist-tag:TRAINEE",
+    "waitlist-view",
+    "wiki-editor",
+];
+
+async fn validate_permissions(
+    tx: &mut crate::DBTX<'_>,
+    permissions: &[String],
+) -> Result<(), Madness> {
+    for permission in permissions {
+        if ACCESS_KEYS.contains(&permission.as_str()) {
+            continue;
+        }
+
+        if let Some(role) = permission.strip_prefix("commanders-manage:") {
+            let exists = sqlx::query!("SELECT name FROM role WHERE name=$1", role)
+                .fetch_optional(&mut *tx)
+                .await?;
+            if exists.is_some() {
+                continue;
+            }
+        }
+
+        return Err(Madness::BadRequest(format!(
+            "Unknown permission \"{}\"",
+            permission
+        )));
+    }
+
+    Ok(())
+}
+
+async fn set_permissions(
+    tx: &mut crate::DBTX<'_>,
+    role: &str,
+    permissions: &[String],
+) -> Result<(), Madness> {
+    validate_permissions(&mut *tx, permissions).await?;
+
+    sqlx::query!("DELETE FROM role_permission WHERE role=$1", role)
+        .execute(&mut *tx)
+        .await?;
+
+    for permission in permissions {
+        sqlx::query!(
+            "INSERT INTO role_permission (role, permission) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+            role,
+            permission
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    Ok(())
+}
+
+#[get("/api/roles")]
+async fn list(
+    account: AuthenticatedAccount,
+    app: &rocket::State<Application>,
+) -> Result<Json<Vec<Role>>, Madness> {
+    account.require_access("roles-manage")?;
+
+    let mut roles = BTreeMap::new();
+    for role in sqlx::query!(
+        "SELECT name, sort_order, (SELECT COUNT(*) FROM admin WHERE admin.role=role.name) AS \"member_count!\"
+        FROM role ORDER BY sort_order ASC"
+    )
+    .fetch_all(app.get_db())
+    .await?
+    {
+        roles.insert(
+            role.name.clone(),
+            Role {
+                name: role.name,
+                sort_order: role.sort_order,
+                member_count: role.member_count,
+                permissions: Vec::new(),
+            },
+        );
+    }
+
+    for permission in sqlx::query!("SELECT role, permission FROM role_permission ORDER BY permission ASC")
+        .fetch_all(app.get_db())
+        .await?
+    {
+        if let Some(role) = roles.get_mut(&permission.role) {
+            role.permissions.push(permission.permission);
+        }
+    }
+
+    let mut roles: Vec<Role> = roles.into_values().collect();
+    roles.sort_by_key(|role| role.sort_order);
+    Ok(Json(roles))
+}
+
+#[post("/api/roles", data = "<body>")]
+async fn create(
+    account: AuthenticatedAccount,
+    app: &rocket::State<Application>,
+    body: Json<RoleRequest>,
+) -> Result<&'static str, Madness> {
+    account.require_access("roles-manage")?;
+
+    if body.name.is_empty() || body.name.len() > 64 {
+        return Err(Madness::BadRequest(
+            "Role names must be between 1 and 64 characters".to_string(),
+        ));
+    }
+
+    let mut tx = app.get_db().begin().await?;
+    let inserted = sqlx::query!(
+        "INSERT INTO role (name, sort_order) VALUES ($1, $2) ON CONFLICT DO NOTHING RETURNING name",
+        body.name,
+        body.sort_order
+    )
+    .fetch_optional(&mut tx)
+    .await?;
+    if inserted.is_none() {
+        return Err(Madness::BadRequest(format!(
+            "The role \"{}\" already exists",
+            body.name
+        )));
+    }
+
+    set_permissions(&mut tx, &body.name, &body.permissions).await?;
+    audit::record(
+        &mut tx,
+        AuditEntry::new(account.id, "role-create", "role", None).after(json!({
+            "name": body.name,
+            "sort_order": body.sort_order,
+            "permissions": body.permissions,
+        })),
+    )
+    .await?;
+    tx.commit().await?;
+
+    Ok("Ok")
+}
+
+#[put("/api/roles/<name>", data = "<body>")]
+async fn update(
+    account: AuthenticatedAccount,
+    app: &rocket::State<Application>,
+    name: String,
+    body: Json<RoleUpdate>,
+) -> Result<&'static str, Madness> {
+    account.require_access("roles-manage")?;
+
+    // Don't let anyone lock themselves out of managing roles
+    if !body.permissions.iter().any(|p| p == "roles-manage") {
+        let others = sqlx::query!(
+            "SELECT COUNT(*) AS \"count!\" FROM role_permission
+            JOIN admin ON admin.role=role_permission.role
+            WHERE admin.character_id=$1 AND permission='roles-manage' AND role_permission.role != $2
+            AND (admin.expires_at IS NULL OR admin.expires_at > EXTRACT(EPOCH FROM NOW()))",
+            account.id,
+            name
+        )
+        .fetch_one(app.get_db())
+        .await?;
+        if others.count == 0 {
+            return Err(Madness::BadRequest(
+                "You cannot remove your own permission to manage roles".to_string(),
+            ));
+        }
+    }
+
+    let mut tx = app.get_db().begin().await?;
+    let previous = sqlx::query!(
+        "SELECT permission FROM role_permission WHERE role=$1 ORDER BY permission ASC",
+        name
+    )
+    .fetch_all(&mut tx)
+    .await?
+    .into_iter()
+    .map(|permission| permission.permission)
+    .collect::<Vec<String>>();
+
+    let updated = sqlx::query!(
+        "UPDATE role SET sort_order=$1 WHERE name=$2",
+        body.sort_order,
+        name
+    )
+    .execute(&mut tx)
+    .await?;
+    if updated.rows_affected() == 0 {
+        return Err(Madness::NotFound("Role not found"));
+    }
+
+    set_permissions(&mut tx, &name, &body.permissions).await?;
+    audit::record(
+        &mut tx,
+        AuditEntry::new(account.id, "role-update", "role", None)
+            .before(json!({ "name": name, "permissions": previous }))
+            .after(json!({
+                "name": name,
+                "sort_order": body.sort_order,
+                "permissions": body.permissions,
+            })),
+    )
+    .await?;
+    tx.commit().await?;
+
+    Ok("Ok")
+}
+
+#[delete("/api/roles/<name>")]
+async fn remove(
+    account: AuthenticatedAccount,
+    app: &rocket::State<Application>,
+    name: String,
+) -> Result<&'static str, Madness> {
+    account.require_access("roles-manage")?;
+
+    let members = sqlx::query!(
+        "SELECT COUNT(*) AS \"count!\" FROM admin WHERE role=$1",
+        name
+    )
+    .fetch_one(app.get_db())
+    .await?;
+    if members.count > 0 {
+        return Err(Madness::BadRequest(format!(
+            "The role \"{}\" is still held by {} character(s)",
+            name, members.count
+        )));
+    }
+
+    let mut tx = app.get_db().begin().await?;
+    let deleted = sqlx::query!("DELETE FROM role WHERE name=$1", name)
+        .execute(&mut tx)
+        .await?;
+    if deleted.rows_affected() == 0 {
+        return Err(Madness::NotFound("Role not found"));
+    }
+
+    audit::record(
+        &mut tx,
+        AuditEntry::new(account.id, "role-delete", "role", None).before(json!({ "name": name })),
+    )
+    .await?;
+    tx.commit().await?;
+
+    Ok("Ok")
+}
+
+pub fn routes() -> Vec<rocket::Route> {
+    routes![
+        list,   //  GET     /api/roles
+        create, //  POST    /api/roles
+        update, //  PUT     /api/roles/<name>
+        remove  //  DELETE  /api/roles/<name>
+    ]
+}
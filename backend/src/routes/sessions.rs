@@ -0,0 +1,89 @@
+use rocket::serde::json::Json;
+use serde::Serialize;
+
+use crate::{app::Application, core::auth::AuthenticatedAccount, util::madness::Madness};
+
+#[derive(Debug, Serialize)]
+struct Session {
+    id: i64,
+    created_at: i64,
+    last_seen: i64,
+    user_agent: Option<String>,
+    current: bool,
+}
+
+#[get("/api/sessions")]
+async fn list(
+    account: AuthenticatedAccount,
+    app: &rocket::State<Application>,
+) -> Result<Json<Vec<Session>>, Madness> {
+    account.require_session()?;
+
+    let sessions = sqlx::query!(
+        "SELECT id, created_at, last_seen, user_agent FROM session WHERE character_id=$1 ORDER BY last_seen DESC",
+        account.id
+    )
+    .fetch_all(app.get_db())
+    .await?
+    .into_iter()
+    .map(|session| Session {
+        current: account.session_id == Some(session.id),
+        id: session.id,
+        created_at: session.created_at,
+        last_seen: session.last_seen,
+        user_agent: session.user_agent,
+    })
+    .collect();
+
+    Ok(Json(sessions))
+}
+
+#[delete("/api/sessions/<session_id>")]
+async fn revoke(
+    account: AuthenticatedAccount,
+    app: &rocket::State<Application>,
+    session_id: i64,
+) -> Result<&'static str, Madness> {
+    account.require_session()?;
+
+    let result = sqlx::query!(
+        "DELETE FROM session WHERE id=$1 AND character_id=$2",
+        session_id,
+        account.id
+    )
+    .execute(app.get_db())
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(Madness::NotFound("Session not found"));
+    }
+
+    Ok("OK")
+}
+
+// Logs out every other browser, the session making the request stays logged in
+#[delete("/api/sessions")]
+async fn revoke_all(
+    account: AuthenticatedAccount,
+    app: &rocket::State<Application>,
+) -> Result<&'static str, Madness> {
+    account.require_session()?;
+
+    sqlx::query!(
+        "DELETE FROM session WHERE character_id=$1 AND ($2::BIGINT IS NULL OR id != $2)",
+        account.id,
+        account.session_id
+    )
+    .execute(app.get_db())
+    .await?;
+
+    Ok("OK")
+}
+
+pub fn routes() -> Vec<rocket::Route> {
+    routes![
+        list,       //  GET     /api/sessions
+        revoke,     //  DELETE  /api/sessions/<session_id>
+        revoke_all  //  DELETE  /api/sessions
+    ]
+}
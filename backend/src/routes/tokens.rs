@@ -0,0 +1,137 @@
+use rocket::serde::json::Json;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::Application,
+    core::auth::{create_api_token, AuthenticatedAccount},
+    util::madness::Madness,
+};
+
+#[derive(Debug, Serialize)]
+struct ApiTokenInfo {
+    id: i64,
+    name: String,
+    scopes: Vec<String>,
+    created_at: i64,
+    expires_at: Option<i64>,
+    last_used_at: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CreateTokenRequest {
+    name: String,
+    scopes: Vec<String>,
+    expires_at: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+struct CreatedToken {
+    id: i64,
+    token: String,
+}
+
+#[get("/api/tokens")]
+async fn list(
+    account: AuthenticatedAccount,
+    app: &rocket::State<Application>,
+) -> Result<Json<Vec<ApiTokenInfo>>, Madness> {
+    account.require_session()?;
+
+    let tokens = sqlx::query!(
+        "SELECT id, name, scopes, created_at, expires_at, last_used_at FROM api_token
+        WHERE character_id=$1 ORDER BY created_at DESC",
+        account.id
+    )
+    .fetch_all(app.get_db())
+    .await?
+    .into_iter()
+    .map(|token| ApiTokenInfo {
+        id: token.id,
+        name: token.name,
+        scopes: token.scopes.split_whitespace().map(String::from).collect(),
+        created_at: token.created_at,
+        expires_at: token.expires_at,
+        last_used_at: token.last_used_at,
+    })
+    .collect();
+
+    Ok(Json(tokens))
+}
+
+#[post("/api/tokens", data = "<body>")]
+async fn create(
+    account: AuthenticatedAccount,
+    app: &rocket::State<Application>,
+    body: Json<CreateTokenRequest>,
+) -> Result<Json<CreatedToken>, Madness> {
+    account.require_session()?;
+
+    if body.name.is_empty() || body.name.len() > 64 {
+        return Err(Madness::BadRequest(
+            "Token names must be between 1 and 64 characters".to_string(),
+        ));
+    }
+
+    // Tokens are limited to a subset of the access keys the account has
+    if let Some(scope) = body.scopes.iter().find(|scope| !account.access.contains(*scope)) {
+        return Err(Madness::Forbidden(format!(
+            "You cannot create a token with the \"{}\" access key",
+            scope
+        )));
+    }
+
+    let now = chrono::Utc::now().timestamp();
+    if body.expires_at.map(|expires_at| expires_at <= now).unwrap_or(false) {
+        return Err(Madness::BadRequest(
+            "The expiry date must be in the future".to_string(),
+        ));
+    }
+
+    let token = sqlx::query!(
+        "INSERT INTO api_token (character_id, name, scopes, created_at, expires_at) VALUES ($1, $2, $3, $4, $5) RETURNING id",
+        account.id,
+        body.name,
+        body.scopes.join(" "),
+        now,
+        body.expires_at
+    )
+    .fetch_one(app.get_db())
+    .await?;
+
+    // The token is only shown once, it can't be recovered later
+    Ok(Json(CreatedToken {
+        id: token.id,
+        token: create_api_token(&app.token_secret, token.id),
+    }))
+}
+
+#[delete("/api/tokens/<token_id>")]
+async fn revoke(
+    account: AuthenticatedAccount,
+    app: &rocket::State<Application>,
+    token_id: i64,
+) -> Result<&'static str, Madness> {
+    account.require_session()?;
+
+    let result = sqlx::query!(
+        "DELETE FROM api_token WHERE id=$1 AND character_id=$2",
+        token_id,
+        account.id
+    )
+    .execute(app.get_db())
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(Madness::NotFound("Token not found"));
+    }
+
+    Ok("OK")
+}
+
+pub fn routes() -> Vec<rocket::Route> {
+    routes![
+        list,   //  GET     /api/tokens
+        create, //  POST    /api/tokens
+        revoke  //  DELETE  /api/tokens/<token_id>
+    ]
+}
@@ -1,10 +1,15 @@
 use eve_data_core::{TypeDB, TypeID};
 use rocket::serde::json::Json;
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 
 use crate::{
     app::Application,
-    core::{auth::AuthenticatedAccount, sse::Event},
+    core::{
+        audit::{self, AuditEntry},
+        auth::AuthenticatedAccount,
+        sse::Event,
+    },
     util::madness::Madness,
 };
 
@@ -21,12 +26,28 @@ async fn approve_fit(
 ) -> Result<&'static str, Madness> {
     account.require_access("waitlist-manage")?;
 
-    sqlx::query!(
-        "UPDATE waitlist_entry_fit SET state='approved' WHERE id=$1",
+    // The previous state comes back from the update itself, for the audit log
+    let mut tx = app.get_db().begin().await?;
+    let fit = match sqlx::query!(
+        "UPDATE waitlist_entry_fit AS wef SET state='approved' FROM waitlist_entry_fit AS previous
+        WHERE wef.id=$1 AND previous.id=wef.id RETURNING wef.character_id, previous.state",
         input.id
     )
-    .execute(app.get_db())
+    .fetch_optional(&mut tx)
+    .await?
+    {
+        Some(fit) => fit,
+        None => return Err(Madness::NotFound("Fit not found")),
+    };
+
+    audit::record(
+        &mut tx,
+        AuditEntry::new(account.id, "waitlist-approve", "waitlist_entry_fit", Some(input.id))
+            .before(json!({ "character_id": fit.character_id, "state": fit.state }))
+            .after(json!({ "character_id": fit.character_id, "state": "approved" })),
+    )
     .await?;
+    tx.commit().await?;
 
     super::notify::notify_waitlist_update(app).await?;
 
@@ -55,7 +76,7 @@ async fn reject_fit(
 
     let entry = sqlx::query!(
         "
-            SELECT account_id, entry_id, fit_id FROM waitlist_entry_fit wef
+            SELECT account_id, entry_id, fit_id, character_id, state FROM waitlist_entry_fit wef
             JOIN waitlist_entry we ON we.id=wef.entry_id WHERE wef.id=$1
         ",
         input.id
@@ -63,13 +84,27 @@ async fn reject_fit(
     .fetch_one(app.get_db())
     .await?;
 
+    let mut tx = app.get_db().begin().await?;
     sqlx::query!(
         "UPDATE waitlist_entry_fit SET state='rejected', review_comment=$1 WHERE id=$2",
         input.review_comment,
         input.id
     )
-    .execute(app.get_db())
+    .execute(&mut tx)
+    .await?;
+
+    audit::record(
+        &mut tx,
+        AuditEntry::new(account.id, "waitlist-reject", "waitlist_entry_fit", Some(input.id))
+            .before(json!({ "character_id": entry.character_id, "state": entry.state }))
+            .after(json!({
+                "character_id": entry.character_id,
+                "state": "rejected",
+                "review_comment": input.review_comment,
+            })),
+    )
     .await?;
+    tx.commit().await?;
 
     let fit = sqlx::query!("SELECT hull FROM fitting WHERE id=$1", entry.fit_id)
         .fetch_one(app.get_db())
@@ -1,4 +1,13 @@
-use crate::{app::Application, core::auth::AuthenticatedAccount, util::madness::Madness};
+use serde_json::json;
+
+use crate::{
+    app::Application,
+    core::{
+        audit::{self, AuditEntry},
+        auth::AuthenticatedAccount,
+    },
+    util::madness::Madness,
+};
 
 #[delete("/api/waitlist")]
 async fn empty_waitlist(
@@ -9,6 +18,16 @@ async fn empty_waitlist(
 
     let mut tx = app.get_db().begin().await?;
 
+    let entries = sqlx::query!("SELECT COUNT(*) AS \"count!\" FROM waitlist_entry")
+        .fetch_one(&mut tx)
+        .await?;
+    audit::record(
+        &mut tx,
+        AuditEntry::new(account.id, "waitlist-empty", "waitlist", None)
+            .before(json!({ "entries": entries.count })),
+    )
+    .await?;
+
     sqlx::query!("DELETE FROM waitlist_entry_fit")
         .execute(&mut tx)
         .await?;
@@ -6,7 +6,7 @@ use serde::Deserialize;
 use crate::{
     app::Application,
     core::auth::{authorize_character, AuthenticatedAccount},
-    data::{implants, skills},
+    data::{implants, saved_fittings, skills},
     tdf,
     util::madness::Madness,
 };
@@ -21,11 +21,16 @@ struct DnaXup {
 #[derive(Debug, Deserialize)]
 struct XupRequest {
     character_id: i64,
+    #[serde(default)]
     eft: String,
     is_alt: bool,
 
     #[serde(default)]
     dna: Vec<DnaXup>,
+
+    /// In-game saved fitting ids of `character_id`
+    #[serde(default)]
+    saved: Vec<i64>,
 }
 
 const MAX_X_PER_ACCOUNT: usize = 10;
@@ -75,20 +80,6 @@ async fn dedup_dna(db: &mut crate::DBTX<'_>, hull: TypeID, dna: &str) -> Result<
     Ok(result)
 }
 
-async fn get_time_in_fleet(db: &crate::DB, character_id: i64) -> Result<i64, sqlx::Error> {
-    #[derive(sqlx::FromRow)]
-    struct TimeResult {
-        seconds: Option<i64>,
-    }
-    let result: TimeResult = sqlx::query_as::<_, TimeResult>(
-        "SELECT  CAST(SUM(last_seen - first_seen) as bigint) AS seconds FROM fleet_activity WHERE character_id=$1",
-    )
-    .bind(character_id)
-    .fetch_one(db)
-    .await?;
-    Ok(result.seconds.unwrap_or(0))
-}
-
 async fn xup_multi(
     app: &Application,
     account: AuthenticatedAccount,
@@ -132,6 +123,8 @@ async fn xup_multi(
 
             if entity.category == "Character" {
                 err = "You cannot join fleet as your character is banned.";
+            } else if entity.category == "Account" {
+                err = "You cannot join fleet as your account is banned.";
             } else if entity.category == "Corporation" {
                 err = "You cannot join fleet as your corporation is banned.";
             } else if entity.category == "Alliance" {
@@ -143,7 +136,7 @@ async fn xup_multi(
             return Err(Madness::BadRequest(err.to_string()));
         }
 
-        let time_in_fleet = get_time_in_fleet(app.get_db(), character_id).await?;
+        let time_in_fleet = app.time_in_fleet.character_time(character_id).await?.total;
         let implants = implants::get_implants(app, character_id).await?;
         let skills = skills::load_skills(&app.esi_client, app.get_db(), character_id).await?;
 
@@ -158,7 +151,7 @@ async fn xup_multi(
                 implants,
                 time_in_fleet: *time_in_fleet,
                 skills,
-                access_keys: account.access,
+                access_keys: &account.access,
                 id: character_id
             },
         );
@@ -289,6 +282,23 @@ async fn xup(
         xups.push((dna_xup.character_id, fit));
     }
 
+    // Saved fitting x'es
+    if !input.saved.is_empty() {
+        authorize_character(app.get_db(), &account, input.character_id, None).await?;
+        let fittings =
+            saved_fittings::get_saved_fittings(&app.esi_client, input.character_id).await?;
+
+        for fitting_id in &input.saved {
+            let saved = match fittings.iter().find(|f| f.fitting_id == *fitting_id) {
+                Some(saved) => saved,
+                None => return Err(Madness::NotFound("Saved fitting not found")),
+            };
+            let fit = saved.to_fitting()?;
+            fit.validate()?;
+            xups.push((input.character_id, fit));
+        }
+    }
+
     xup_multi(app, account, xups, input.is_alt).await?;
 
     Ok("OK")
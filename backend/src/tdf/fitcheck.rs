@@ -3,9 +3,14 @@ use std::{
     collections::{BTreeMap, BTreeSet},
 };
 use reqwest::Method;
-use super::{fitmatch, implantmatch, skills::SkillTier};
-use crate::data::{categories, fits::DoctrineFit, skills::Skills};
-use eve_data_core::{FitError, Fitting, TypeDB, TypeID};
+use super::{fitmatch, implantmatch, milestones, skills::SkillTier};
+use crate::data::{
+    categories,
+    fitdiffer::{FitDiffer, SectionDiff},
+    fits::DoctrineFit,
+    skills::Skills,
+};
+use eve_data_core::{FitError, FitStats, Fitting, FittingStats, TypeDB, TypeID};
 use serde::Serialize;
 use inflector::Inflector;
 use std::time::Duration;
@@ -16,6 +21,8 @@ pub struct Output {
     pub tags: Vec<&'static str>,
     pub category: String,
     pub errors: Vec<String>,
+    /// Problems an FC should look at, these don't stop the x-up
+    pub warnings: Vec<String>,
 
     pub analysis: Option<PubAnalysis>,
 }
@@ -27,6 +34,49 @@ pub struct PubAnalysis {
     extra: BTreeMap<TypeID, i64>,
     cargo_missing: BTreeMap<TypeID, i64>,
     downgraded: BTreeMap<TypeID, BTreeMap<TypeID, i64>>,
+    slots: BTreeMap<&'static str, SectionDiff>,
+    charge_missing: BTreeMap<TypeID, i64>,
+    charge_swapped: BTreeMap<TypeID, BTreeMap<TypeID, i64>>,
+    stats: Option<PubStats>,
+    doctrine_stats: Option<PubStats>,
+    suggestion: Option<PubSuggestion>,
+}
+
+/// The pilot's fit with the doctrine diff applied
+#[derive(Debug, Serialize)]
+pub struct PubSuggestion {
+    eft: String,
+    dna: String,
+    /// Suggested modules the pilot does not have the skills for
+    missing_skills: Vec<TypeID>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PubStats {
+    resists: BTreeMap<&'static str, [f64; 4]>,
+    ehp: f64,
+    dps: f64,
+    cap_stable_at: Option<f64>,
+    cap_lasts: Option<f64>,
+    speed: f64,
+}
+
+impl From<FitStats> for PubStats {
+    fn from(stats: FitStats) -> Self {
+        let mut resists = BTreeMap::new();
+        resists.insert("shield", stats.resists.shield);
+        resists.insert("armor", stats.resists.armor);
+        resists.insert("hull", stats.resists.hull);
+
+        PubStats {
+            resists,
+            ehp: stats.ehp,
+            dps: stats.dps,
+            cap_stable_at: stats.cap_stable_at,
+            cap_lasts: stats.cap_lasts,
+            speed: stats.speed,
+        }
+    }
 }
 
 pub struct PilotData<'a> {
@@ -44,39 +94,66 @@ pub struct FitChecker<'a> {
     fit: &'a Fitting,
     doctrine_fit: Option<&'static DoctrineFit>,
     pilot: &'a PilotData<'a>,
+    suggested_fit: Option<Fitting>,
 
     tags: BTreeSet<&'static str>,
     errors: Vec<String>,
+    warnings: Vec<String>,
     analysis: Option<PubAnalysis>,
 }
 
 impl<'a> FitChecker<'a> {
-    pub async fn check(
-        pilot: &PilotData<'_>,
-        fit: &Fitting,
-        badges: &Vec<String>,
-    ) -> Result<Output, FitError> {
-        let mut checker = FitChecker {
+    fn new(
+        pilot: &'a PilotData<'a>,
+        fit: &'a Fitting,
+        badges: &'a Vec<String>,
+    ) -> FitChecker<'a> {
+        FitChecker {
             approved: true,
             category: None,
             badges,
             fit,
             doctrine_fit: None,
             pilot,
+            suggested_fit: None,
             tags: BTreeSet::new(),
             errors: Vec::new(),
+            warnings: Vec::new(),
             analysis: None,
-        };
+        }
+    }
+
+    /// The checks that decide the skill, fit, implant and specialist tags
+    fn check_tags(&mut self) -> Result<(), FitError> {
+        self.check_skill_reqs()?;
+        self.check_fit();
+        self.add_snowflake_tags();
+        self.add_implant_tag();
+        Ok(())
+    }
+
+    /// Everything that only depends on the fit, skills, implants and badges
+    fn run_checks(&mut self) -> Result<(), FitError> {
+        self.check_tags()?;
+        self.check_module_skills()?;
+        self.check_fitting_resources()?;
+        self.add_fit_stats()?;
+        self.add_fit_suggestion()?;
+        self.check_fit_reqs();
+        self.check_fit_implants_reqs();
+        self.check_logi_implants();
+        self.set_category();
+        Ok(())
+    }
+
+    pub async fn check(
+        pilot: &PilotData<'_>,
+        fit: &Fitting,
+        badges: &Vec<String>,
+    ) -> Result<Output, FitError> {
+        let mut checker = FitChecker::new(pilot, fit, badges);
 
-        checker.check_skill_reqs()?;
-        checker.check_module_skills()?;
-        checker.check_fit();
-        checker.check_fit_reqs();
-        checker.check_fit_implants_reqs();
-        checker.check_logi_implants();
-        checker.set_category();
-        checker.add_snowflake_tags();
-        checker.add_implant_tag();
+        checker.run_checks()?;
         checker.add_war_tags().await;
         checker.merge_tags();
         checker.check_time_in_fleet();
@@ -84,6 +161,27 @@ impl<'a> FitChecker<'a> {
         checker.finish()
     }
 
+    /// Whether the pilot counts as elite in this fit for the hour milestones
+    pub fn is_elite(
+        pilot: &PilotData<'_>,
+        fit: &Fitting,
+        badges: &Vec<String>,
+    ) -> Result<bool, FitError> {
+        let mut checker = FitChecker::new(pilot, fit, badges);
+
+        checker.check_tags()?;
+        checker.merge_tags();
+
+        Ok(checker.pilot_is_elite())
+    }
+
+    fn pilot_is_elite(&self) -> bool {
+        self.tags.contains("ELITE")
+            || self.tags.contains("ELITE-GOLD")
+            || self.tags.contains("WEB")
+            || self.tags.contains("BASTION")
+    }
+
     fn check_skill_reqs_tier(&self, tier: SkillTier) -> Result<bool, FitError> {
         let ship_name = TypeDB::name_of(self.fit.hull)?;
         if let Some(reqs) = super::skills::skill_data().requirements.get(&ship_name) {
@@ -141,6 +239,20 @@ impl<'a> FitChecker<'a> {
         Ok(())
     }
 
+    // Only part of dogma is evaluated, so problems are shown to the FC and leave approval alone
+    fn check_fitting_resources(&mut self) -> Result<(), FitError> {
+        let stats =
+            FittingStats::calculate(self.fit, &self.pilot.skills.0, self.pilot.implants)?;
+        let problems = stats.problems();
+        if !problems.is_empty() {
+            self.tags.insert("FITTING-PROBLEM");
+        }
+        for problem in problems {
+            self.warnings.push(problem.to_string());
+        }
+        Ok(())
+    }
+
     fn check_logi_implants(&mut self) {
         if self.fit.hull == type_id!("Nestor") && !self.pilot.implants.contains(&type_id!("% EM-806"))
         {
@@ -170,6 +282,9 @@ impl<'a> FitChecker<'a> {
             if fit_ok && doctrine_fit.name.contains("Elite") || doctrine_fit.name.contains("Web Specialist") {
                 self.tags.insert("ELITE-FIT");
             }
+            if !(fit_ok && diff.cargo_missing.is_empty() && diff.module_extra.is_empty()) {
+                self.suggested_fit = Some(FitDiffer::suggest(self.fit, &diff));
+            }
 
             self.analysis = Some(PubAnalysis {
                 name: doctrine_fit.name.clone(),
@@ -177,12 +292,54 @@ impl<'a> FitChecker<'a> {
                 extra: diff.module_extra,
                 downgraded: diff.module_downgraded,
                 cargo_missing: diff.cargo_missing,
+                slots: diff.slots,
+                charge_missing: diff.charge_missing,
+                charge_swapped: diff.charge_swapped,
+                stats: None,
+                doctrine_stats: None,
+                suggestion: None,
             });
         } else {
             self.approved = false;
         }
     }
 
+    fn add_fit_stats(&mut self) -> Result<(), FitError> {
+        if let (Some(analysis), Some(doctrine_fit)) = (&mut self.analysis, self.doctrine_fit) {
+            let skills = &self.pilot.skills.0;
+            analysis.stats = Some(FitStats::calculate(self.fit, skills)?.into());
+            analysis.doctrine_stats = Some(FitStats::calculate(&doctrine_fit.fit, skills)?.into());
+        }
+        Ok(())
+    }
+
+    fn add_fit_suggestion(&mut self) -> Result<(), FitError> {
+        let (analysis, fit) = match (&mut self.analysis, &self.suggested_fit) {
+            (Some(analysis), Some(fit)) => (analysis, fit),
+            _ => return Ok(()),
+        };
+
+        let mut missing_skills = Vec::new();
+        for (&type_id, module) in TypeDB::load_types(&fit.modules.keys().copied().collect::<Vec<_>>())? {
+            let module = module.ok_or(FitError::InvalidModule)?;
+            if module
+                .skill_requirements
+                .iter()
+                .any(|(&skill_id, &level)| self.pilot.skills.get(skill_id) < level)
+            {
+                missing_skills.push(type_id);
+            }
+        }
+        missing_skills.sort_unstable();
+
+        analysis.suggestion = Some(PubSuggestion {
+            eft: fit.to_eft(&analysis.name)?,
+            dna: fit.to_dna()?,
+            missing_skills,
+        });
+        Ok(())
+    }
+
     fn check_fit_reqs(&mut self) {
         let comp_reqs = match self.doctrine_fit {
             Some(fit) => {
@@ -242,48 +399,16 @@ impl<'a> FitChecker<'a> {
     }
 
     fn check_time_in_fleet(&mut self) {
-        let pilot_is_elite = self.tags.contains("ELITE")
-        || self.tags.contains("ELITE-GOLD")
-        || self.tags.contains("WEB")
-        || self.tags.contains("BASTION");
-
-        let has_t2_blaster = self.fit.modules.get(&type_id!("Neutron Blaster Cannon II")).copied().unwrap_or(0) > 0;
-        let has_t2_lasers = self.fit.modules.get(&type_id!("Mega Pulse Laser II")).copied().unwrap_or(0) > 0;
-
-        // Oneiros pilots only have one upgrade milestone. Elite by 105H
-        if self.fit.hull == type_id!("Oneiros") {
-            if self.pilot.time_in_fleet >= (105 * 3600) && !pilot_is_elite {
-                self.tags.insert("ELITE-HOURS-REACHED");
-            }
-        }
-        // The Megathron and N. Apoc pilots only have one upgrade milestone. Get out of the hull by 22H
-        else if self.fit.hull == type_id!("Megathron") || self.fit.hull == type_id!("Apocalypse Navy Issue") {
-            if self.pilot.time_in_fleet >= (22 * 3600) {
-              self.tags.insert("UPGRADE-HOURS-REACHED");
-            }
-        }
-        // All other pilots are subject to multiple checks; however, we only want to check DPS ships.
-        else if self.fit.hull == type_id!("Kronos") || self.fit.hull == type_id!("Nightmare") || self.fit.hull == type_id!("Paladin") || self.fit.hull == type_id!("Vindicator") {
-            if self.pilot.time_in_fleet >= (220 * 3600) && !pilot_is_elite {
-                self.tags.insert("ELITE-HOURS-REACHED");
-            }
-            else if self.pilot.time_in_fleet >= (130 * 3600) {
-                // Vindicator requires the Web Badge by 130H
-                if self.fit.hull == type_id!("Vindicator") {
-                    if !self.badges.contains(&String::from("WEB")) {
-                        self.tags.insert("UPGRADE-HOURS-REACHED");
-                    }
-                // and Marauders require T2 guns
-                } else if !((self.fit.hull == type_id!("Kronos") && has_t2_blaster) || (self.fit.hull == type_id!("Paladin") && has_t2_lasers)) {
-                    self.tags.insert("UPGRADE-HOURS-REACHED");
-                }
-            }
-            // By 85H the pilot must be in a marauder or have T2 guns
-            else if self.pilot.time_in_fleet >= (85 * 3600) {
-                if !(self.fit.hull == type_id!("Kronos") || self.fit.hull == type_id!("Paladin") || has_t2_blaster || has_t2_lasers) {
-                    self.tags.insert("UPGRADE-HOURS-REACHED");
-                }
-            }
+        let pilot_is_elite = self.pilot_is_elite();
+
+        if let Some(tag) = milestones::check(
+            self.fit.hull,
+            &self.fit.modules,
+            self.pilot.time_in_fleet,
+            pilot_is_elite,
+            self.badges,
+        ) {
+            self.tags.insert(tag);
         }
 
         if self.tags.contains("ELITE-HOURS-REACHED") || self.tags.contains("UPGRADE-HOURS-REACHED") {
@@ -468,6 +593,7 @@ impl<'a> FitChecker<'a> {
             approved: self.approved,
             tags: self.tags.into_iter().collect(),
             errors: self.errors,
+            warnings: self.warnings,
             category: self.category.expect("Category not assigned"),
             analysis: self.analysis,
         })
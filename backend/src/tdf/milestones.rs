@@ -0,0 +1,115 @@
+use std::collections::BTreeMap;
+
+use eve_data_core::TypeID;
+
+pub const UPGRADE_HOURS_REACHED: &str = "UPGRADE-HOURS-REACHED";
+pub const ELITE_HOURS_REACHED: &str = "ELITE-HOURS-REACHED";
+
+// Hour milestones, see check() for what each one requires
+const ONEIROS_ELITE_HOURS: i64 = 105;
+const STARTER_UPGRADE_HOURS: i64 = 22;
+const DPS_T2_HOURS: i64 = 85;
+const DPS_UPGRADE_HOURS: i64 = 130;
+const DPS_ELITE_HOURS: i64 = 220;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Milestone {
+    pub hours: i64,
+    pub tag: &'static str,
+}
+
+fn is_dps_hull(hull: TypeID) -> bool {
+    hull == type_id!("Kronos")
+        || hull == type_id!("Nightmare")
+        || hull == type_id!("Paladin")
+        || hull == type_id!("Vindicator")
+}
+
+/// All the milestones that apply to a hull, in order
+pub fn milestones(hull: TypeID) -> Vec<Milestone> {
+    if hull == type_id!("Oneiros") {
+        vec![Milestone {
+            hours: ONEIROS_ELITE_HOURS,
+            tag: ELITE_HOURS_REACHED,
+        }]
+    } else if hull == type_id!("Megathron") || hull == type_id!("Apocalypse Navy Issue") {
+        vec![Milestone {
+            hours: STARTER_UPGRADE_HOURS,
+            tag: UPGRADE_HOURS_REACHED,
+        }]
+    } else if is_dps_hull(hull) {
+        vec![
+            Milestone {
+                hours: DPS_T2_HOURS,
+                tag: UPGRADE_HOURS_REACHED,
+            },
+            Milestone {
+                hours: DPS_UPGRADE_HOURS,
+                tag: UPGRADE_HOURS_REACHED,
+            },
+            Milestone {
+                hours: DPS_ELITE_HOURS,
+                tag: ELITE_HOURS_REACHED,
+            },
+        ]
+    } else {
+        vec![]
+    }
+}
+
+/// The next milestone for a hull that the pilot has not reached yet
+pub fn next_milestone(hull: TypeID, time_in_fleet: i64) -> Option<Milestone> {
+    milestones(hull)
+        .into_iter()
+        .find(|milestone| time_in_fleet < milestone.hours * 3600)
+}
+
+/// Returns the tag for the milestone the pilot has reached but not yet met the requirements of
+pub fn check(
+    hull: TypeID,
+    modules: &BTreeMap<TypeID, i64>,
+    time_in_fleet: i64,
+    pilot_is_elite: bool,
+    badges: &[String],
+) -> Option<&'static str> {
+    let has_t2_blaster = modules.get(&type_id!("Neutron Blaster Cannon II")).copied().unwrap_or(0) > 0;
+    let has_t2_lasers = modules.get(&type_id!("Mega Pulse Laser II")).copied().unwrap_or(0) > 0;
+
+    // Oneiros pilots only have one upgrade milestone. Elite by 105H
+    if hull == type_id!("Oneiros") {
+        if time_in_fleet >= (ONEIROS_ELITE_HOURS * 3600) && !pilot_is_elite {
+            return Some(ELITE_HOURS_REACHED);
+        }
+    }
+    // The Megathron and N. Apoc pilots only have one upgrade milestone. Get out of the hull by 22H
+    else if hull == type_id!("Megathron") || hull == type_id!("Apocalypse Navy Issue") {
+        if time_in_fleet >= (STARTER_UPGRADE_HOURS * 3600) {
+            return Some(UPGRADE_HOURS_REACHED);
+        }
+    }
+    // All other pilots are subject to multiple checks; however, we only want to check DPS ships.
+    else if is_dps_hull(hull) {
+        if time_in_fleet >= (DPS_ELITE_HOURS * 3600) && !pilot_is_elite {
+            return Some(ELITE_HOURS_REACHED);
+        }
+        else if time_in_fleet >= (DPS_UPGRADE_HOURS * 3600) {
+            // Vindicator requires the Web Badge by 130H
+            if hull == type_id!("Vindicator") {
+                if !badges.iter().any(|badge| badge == "WEB") {
+                    return Some(UPGRADE_HOURS_REACHED);
+                }
+            // and Marauders require T2 guns
+            } else if !((hull == type_id!("Kronos") && has_t2_blaster) || (hull == type_id!("Paladin") && has_t2_lasers)) {
+                return Some(UPGRADE_HOURS_REACHED);
+            }
+        }
+        // By 85H the pilot must be in a marauder or have T2 guns
+        else if time_in_fleet >= (DPS_T2_HOURS * 3600) {
+            if !(hull == type_id!("Kronos") || hull == type_id!("Paladin") || has_t2_blaster || has_t2_lasers) {
+                return Some(UPGRADE_HOURS_REACHED);
+            }
+        }
+    }
+
+    None
+}
@@ -1,4 +1,5 @@
 pub mod fitcheck;
 mod fitmatch;
 mod implantmatch;
+pub mod milestones;
 pub mod skills;
@@ -1,7 +1,8 @@
 use std::io::Cursor;
 
-use rocket::http::Status;
+use rocket::http::{ContentType, Status};
 use rocket::Response;
+use serde_json::json;
 use zxcvbn::ZxcvbnError;
 
 use crate::core::esi::ESIError;
@@ -56,7 +57,8 @@ impl From<SkillsError> for Madness {
 impl<'r> rocket::response::Responder<'r, 'static> for Madness {
     fn respond_to(self, _: &'r rocket::request::Request<'_>) -> rocket::response::Result<'static> {
         let status = match &self {
-            Self::AccessDenied | Self::ESIError(ESIError::MissingScope | ESIError::NoToken) => {
+            Self::AccessDenied
+            | Self::ESIError(ESIError::MissingScope | ESIError::NoToken | ESIError::TokenRevoked) => {
                 Status::Unauthorized
             }
 
@@ -81,6 +83,24 @@ impl<'r> rocket::response::Responder<'r, 'static> for Madness {
         }
 
         let error = format!("{}", self);
+
+        // EFT errors point at a line of the paste, so let the frontend highlight it
+        if let Self::FitError(FitError::EftLine(eft)) = &self {
+            let body = json!({
+                "error": error,
+                "line": eft.line,
+                "text": eft.text,
+                "reason": eft.reason,
+                "suggestions": eft.suggestions,
+            })
+            .to_string();
+            return Ok(Response::build()
+                .header(ContentType::JSON)
+                .sized_body(body.len(), Cursor::new(body))
+                .status(status)
+                .finalize());
+        }
+
         Ok(Response::build()
             .sized_body(error.len(), Cursor::new(error))
             .status(status)
@@ -100,7 +100,9 @@ export const icons = {
   UNKNOWN: { type: "shield", color: "neutral", letter: "?", name: null },
   "AT-WAR": {type: "image", href: require('./BadgeImages/Wars.png'),  name: 'Pilot at war'},
   "FACTION-WAR": {type: "image", href: require('./BadgeImages/Icon_fw.png'), name: 'Pilot in FW'},
-  BOXER: {type: 'shield', color: 'neutral', letter: 'B', name: 'Trusted Boxer'}
+  BOXER: {type: 'shield', color: 'neutral', letter: 'B', name: 'Trusted Boxer'},
+  "POSSIBLE-EVASION": {type: 'shield', color: 'red', letter: '!', name: 'Possible ban evasion'},
+  "FITTING-PROBLEM": {type: 'shield', color: 'yellow', letter: 'F', name: 'Fit exceeds slots, hardpoints or fitting resources'}
 };
 
 const BadgeIcon = ({ type = "UNKNOWN", height = "1.2em" }) => {
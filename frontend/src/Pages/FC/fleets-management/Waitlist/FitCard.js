@@ -173,6 +173,7 @@ const FitCard = ({ fit, bossId, tab, inviteCounts, onInvite, max_alts }) => {
   const ContentContainer = ({ character, fit_analysis, id, tags, bossId, inviteCounts, onInvite, skills, max_alts }) => {
     const ALLOWED_TAGS = [
       'NO-EM-806',
+      'FITTING-PROBLEM',
       'SLOW',
       'STARTER',
       'UPGRADE-HOURS-REACHED',
@@ -180,7 +181,8 @@ const FitCard = ({ fit, bossId, tab, inviteCounts, onInvite, max_alts }) => {
       "AT-WAR",
       "FACTION-WAR",
       'NON-DOCTRINE',
-      'BOXER'
+      'BOXER',
+      'POSSIBLE-EVASION'
     ];
 
     tags = tags.filter(tag => ALLOWED_TAGS.includes(tag));
@@ -172,6 +172,11 @@ const Fitcheck = () => {
           return (
             !fit.approved && (
               <div style={{ marginBottom: "20px" }} key={key}>
+                {fit.warnings?.map((warning, i) => (
+                  <p key={i} style={{ color: 'orange' }}>
+                    <FontAwesomeIcon fixedWidth icon={faTimes} /> {warning}
+                  </p>
+                ))}
                 <FitDisplay fit={fit} />
               </div>
             )
@@ -34,6 +34,11 @@ export async function apiCall(path, { json, ...options }) {
   return decoded;
 }
 
+// Most errors are plain text, some (like EFT parse errors) are JSON with an `error` message
+export function errorMessage(e) {
+  return e && e.error ? e.error : e.toString();
+}
+
 export async function toaster(toastContext, promise) {
   try {
     const result = await promise;
@@ -44,7 +49,7 @@ export async function toaster(toastContext, promise) {
   } catch (e) {
     console.error(e);
     addToast(toastContext, {
-      message: e.toString(),
+      message: errorMessage(e),
       variant: "danger",
     });
   }
@@ -57,7 +62,7 @@ export async function errorToaster(toastContext, promise) {
     console.error(e);
     addToast(toastContext, {
       title: "Error",
-      message: e.toString(),
+      message: errorMessage(e),
       variant: "danger",
     });
   }